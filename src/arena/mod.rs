@@ -0,0 +1,317 @@
+//
+// Offline match runner. Plays games between snakes locally, using the rules engine in
+// place of the official game server.
+//
+
+mod remote;
+pub mod rating;
+pub mod tournament;
+pub mod tuning;
+
+pub use remote::RemotePlayer;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Instant;
+
+use rand::Rng;
+
+use crate::snake::api::{SnakeConfig, Game, Snake, DEFAULT_TIMEOUT};
+use crate::snake::rules::{Rules, EliminationCause};
+use crate::snake::strategy::Strategy;
+use crate::snake::utils::Move;
+use crate::snake::{handle_start, handle_move, handle_end, Appearance};
+
+/// Something that controls a snake: one of our strategies, or a snake server somewhere.
+pub trait Player: Send + Sync {
+
+    fn start(&self, request: &SnakeConfig);
+
+    /// Returns the move the player picked, or None if it didn't pick one (or picked one
+    /// too slowly), in which case the snake makes its default move.
+    fn next_move(&self, request: &SnakeConfig) -> Option<Move>;
+
+    fn end(&self, request: &SnakeConfig);
+
+}
+
+/// Plays one of our strategies in process, through the same handlers as the snake server.
+pub struct LocalPlayer {
+    strategy: Box<dyn Strategy>,
+    appearance: Appearance,
+}
+
+impl LocalPlayer {
+    pub fn new(strategy: Box<dyn Strategy>) -> LocalPlayer {
+        LocalPlayer {
+            strategy,
+            appearance: Default::default(),
+        }
+    }
+}
+
+impl Player for LocalPlayer {
+
+    fn start(&self, request: &SnakeConfig) {
+        handle_start(self.strategy.as_ref(), &self.appearance, request.clone());
+    }
+
+    fn next_move(&self, request: &SnakeConfig) -> Option<Move> {
+        let response = handle_move(self.strategy.as_ref(), request.clone(), Instant::now());
+        response.r#move.parse().ok()
+    }
+
+    fn end(&self, request: &SnakeConfig) {
+        handle_end(self.strategy.as_ref(), request.clone());
+    }
+
+}
+
+/// A snake taking part in a game. Competitors are cheap to clone, and clones share the
+/// same player.
+#[derive(Clone)]
+pub struct Competitor {
+    pub name: String,
+    pub player: Arc<dyn Player>,
+}
+
+/// How games are set up.
+pub struct Settings {
+    pub width: u32,
+    pub height: u32,
+    pub rules: Rules,
+
+    /// Time (in milliseconds) players have to respond to each request.
+    pub timeout: u32,
+
+    /// Most turns a game goes on for. Games still going by then are a draw, since snakes
+    /// that never get in each other's way (or a lone snake chasing its tail) could
+    /// otherwise keep going forever.
+    pub max_turns: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            width: 11,
+            height: 11,
+            rules: Default::default(),
+            timeout: DEFAULT_TIMEOUT,
+            max_turns: 1000,
+        }
+    }
+}
+
+/// How a snake left the game.
+#[derive(Debug, Clone)]
+pub struct Death {
+    /// Index of the snake in the list of competitors.
+    pub snake: usize,
+    pub turn: u32,
+    pub cause: EliminationCause,
+    /// Index of the snake responsible, if any.
+    pub by: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GameResult {
+    /// Index of the last snake standing (or one of the last squad standing), if any.
+    /// Nobody wins a game where the last snakes are all eliminated on the same turn, or a
+    /// game that runs out of turns.
+    pub winner: Option<usize>,
+    /// Number of turns played.
+    pub turns: u32,
+    /// Snakes eliminated during the game, in order.
+    pub deaths: Vec<Death>,
+}
+
+impl GameResult {
+
+    /// Number of turns a snake survived for.
+    pub fn survival(&self, snake: usize) -> u32 {
+        match self.deaths.iter().find(|death| death.snake == snake) {
+            Some(death) => death.turn,
+            None => self.turns,
+        }
+    }
+
+}
+
+impl fmt::Display for EliminationCause {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            EliminationCause::OutOfHealth => "starved",
+            EliminationCause::OutOfBounds => "hit a wall",
+            EliminationCause::SelfCollision => "ran into itself",
+            EliminationCause::BodyCollision => "ran into",
+            EliminationCause::HeadToHeadCollision => "lost a head-to-head with",
+            EliminationCause::SquadEliminated => "went down with",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+/// ID the rules engine knows a competitor by.
+fn snake_id(idx: usize) -> String {
+    format!("snake-{}", idx)
+}
+
+fn snake_idx(id: &str) -> usize {
+    id["snake-".len()..].parse().unwrap()
+}
+
+/// Request sent to a competitor, from its point of view.
+fn request_for(state: &SnakeConfig, you: &Snake) -> SnakeConfig {
+    SnakeConfig {
+        you: you.clone(),
+        ..state.clone()
+    }
+}
+
+/// Plays a game between competitors until the rules say it's over: usually when at most
+/// one of them (or one squad) is left standing, or none are, in a game with a single
+/// competitor. Games that reach the turn limit end there, as a draw.
+pub fn play_game<R: Rng>(competitors: &[Competitor], settings: &Settings, rng: &mut R) -> GameResult {
+
+    let snakes: Vec<(String, String)> = competitors.iter().enumerate()
+        .map(|(idx, competitor)| (snake_id(idx), competitor.name.clone()))
+        .collect();
+
+    let board = settings.rules.create_board(settings.width, settings.height, &snakes, rng);
+
+    let mut state = SnakeConfig {
+        game: Game {
+            id: format!("local-{:016x}", rng.gen::<u64>()),
+            ruleset: settings.rules.ruleset(),
+            timeout: Some(settings.timeout),
+        },
+        turn: 0,
+        you: board.snakes[0].clone(),
+        board,
+    };
+
+    // Last state seen of each snake, so eliminated snakes can be told how they ended up
+    let mut last_seen: Vec<Snake> = state.board.snakes.clone();
+
+    for (competitor, snake) in competitors.iter().zip(last_seen.iter()) {
+        competitor.player.start(&request_for(&state, snake));
+    }
+
+    let mut deaths = Vec::new();
+
+    // A single competitor plays until it's eliminated, whatever the mode
+    let is_over = |state: &SnakeConfig| match competitors.len() {
+        1 => state.board.snakes.is_empty(),
+        _ => settings.rules.is_over(state),
+    };
+
+    while !is_over(&state) && state.turn < settings.max_turns {
+        let mut moves = HashMap::new();
+        for snake in state.board.snakes.iter() {
+            let competitor = &competitors[snake_idx(&snake.id)];
+
+            // Snakes left out of the moves make their default move
+            if let Some(move_val) = competitor.player.next_move(&request_for(&state, snake)) {
+                moves.insert(snake.id.clone(), move_val);
+            }
+        }
+
+        let (next, eliminations) = settings.rules.next_turn(&state, &moves, rng);
+        state = next;
+
+        for snake in state.board.snakes.iter() {
+            last_seen[snake_idx(&snake.id)] = snake.clone();
+        }
+
+        deaths.extend(eliminations.iter().map(|elimination| Death {
+            snake: snake_idx(&elimination.id),
+            turn: state.turn,
+            cause: elimination.cause,
+            by: elimination.by.as_deref().map(snake_idx),
+        }));
+    }
+
+    for (competitor, snake) in competitors.iter().zip(last_seen.iter()) {
+        competitor.player.end(&request_for(&state, snake));
+    }
+
+    // Nobody wins a game that ran out of turns
+    let winner = if is_over(&state) {
+        state.board.snakes.first().map(|snake| snake_idx(&snake.id))
+    }
+    else {
+        None
+    };
+
+    GameResult {
+        winner,
+        turns: state.turn,
+        deaths,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::snake::strategy::TailChaser;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn tail_chasers(count: usize) -> Vec<Competitor> {
+        (0..count).map(|idx| Competitor {
+            name: format!("tailchaser-{}", idx),
+            player: Arc::new(LocalPlayer::new(Box::new(TailChaser))),
+        }).collect()
+    }
+
+    #[test]
+    fn plays_game_to_the_end() {
+        let competitors = tail_chasers(4);
+        let result = play_game(&competitors, &Default::default(), &mut StdRng::seed_from_u64(1));
+
+        // Everyone but the winner should have died, in turn order
+        let survivors = if result.winner.is_some() { 1 } else { 0 };
+        assert_eq!(result.deaths.len() + survivors, competitors.len());
+        assert!(result.deaths.windows(2).all(|deaths| deaths[0].turn <= deaths[1].turn));
+
+        if let Some(winner) = result.winner {
+            assert!(result.deaths.iter().all(|death| death.snake != winner));
+        }
+    }
+
+    #[test]
+    fn games_are_reproducible() {
+        let competitors = tail_chasers(2);
+        let first = play_game(&competitors, &Default::default(), &mut StdRng::seed_from_u64(7));
+        let second = play_game(&competitors, &Default::default(), &mut StdRng::seed_from_u64(7));
+
+        assert_eq!(first.winner, second.winner);
+        assert_eq!(first.turns, second.turns);
+    }
+
+    #[test]
+    fn solo_games_run_until_the_snake_dies() {
+        let competitors = tail_chasers(1);
+        let result = play_game(&competitors, &Default::default(), &mut StdRng::seed_from_u64(3));
+
+        assert!(result.winner.is_none());
+        assert_eq!(result.deaths.len(), 1);
+        assert_eq!(result.deaths[0].turn, result.turns);
+    }
+
+    #[test]
+    fn games_out_of_turns_are_a_draw() {
+        let competitors = tail_chasers(2);
+        let settings = Settings { max_turns: 5, ..Default::default() };
+        let result = play_game(&competitors, &settings, &mut StdRng::seed_from_u64(1));
+
+        // Tail chasers stay out of each other's way long after the limit
+        assert_eq!(result.turns, 5);
+        assert!(result.winner.is_none());
+        assert!(result.deaths.is_empty());
+        assert_eq!(result.survival(0), 5);
+    }
+
+}
@@ -0,0 +1,150 @@
+//
+// Elo ratings, kept in a file between tournaments.
+//
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Serialize, Deserialize};
+
+/// Rating given to snakes we haven't seen before.
+pub const INITIAL_RATING: f64 = 1500.0;
+
+/// How far a single game can move a rating.
+const K_FACTOR: f64 = 32.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Rating {
+    pub elo: f64,
+    pub games: u32,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Rating {
+            elo: INITIAL_RATING,
+            games: 0,
+        }
+    }
+}
+
+/// Ratings of snakes, keyed by name.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Ratings {
+    ratings: HashMap<String, Rating>,
+}
+
+impl Ratings {
+
+    /// Loads ratings from a file. A file that doesn't exist yet holds no ratings.
+    pub fn load(path: &Path) -> io::Result<Ratings> {
+        match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(Default::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self).unwrap())
+    }
+
+    pub fn get(&self, name: &str) -> Rating {
+        self.ratings.get(name).copied().unwrap_or_default()
+    }
+
+    /// Updates ratings after a game. `results` pairs the name of every snake in the game
+    /// with how long it lasted. Snakes that lasted equally long drew.
+    ///
+    /// Multiplayer games are rated as a set of head-to-head games between every pair of
+    /// snakes, where outlasting the other snake is a win. Each pair's result is weighted
+    /// so a game moves a rating as much as a single head-to-head game would.
+    pub fn update(&mut self, results: &[(&str, u32)]) {
+        if results.len() < 2 {
+            return;
+        }
+
+        let before: Vec<f64> = results.iter().map(|(name, _)| self.get(name).elo).collect();
+        let weight = K_FACTOR / (results.len() - 1) as f64;
+
+        for (i, (name, lasted)) in results.iter().enumerate() {
+            let mut change = 0.0;
+
+            for (j, (_, other_lasted)) in results.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+
+                let expected = 1.0 / (1.0 + 10f64.powf((before[j] - before[i]) / 400.0));
+                let score = if lasted > other_lasted {
+                    1.0
+                }
+                else if lasted < other_lasted {
+                    0.0
+                }
+                else {
+                    0.5
+                };
+
+                change += weight * (score - expected);
+            }
+
+            let rating = self.ratings.entry(String::from(*name)).or_default();
+            rating.elo += change;
+            rating.games += 1;
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn winner_takes_rating_from_loser() {
+        let mut ratings = Ratings::default();
+        ratings.update(&[("winner", 100), ("loser", 50)]);
+
+        let winner = ratings.get("winner");
+        let loser = ratings.get("loser");
+
+        assert_eq!(winner.elo, INITIAL_RATING + K_FACTOR / 2.0);
+        assert_eq!(loser.elo, INITIAL_RATING - K_FACTOR / 2.0);
+        assert_eq!(winner.games, 1);
+    }
+
+    #[test]
+    fn upsets_move_ratings_further() {
+        let mut ratings = Ratings::default();
+        for _ in 0..10 {
+            ratings.update(&[("strong", 100), ("weak", 50)]);
+        }
+
+        let strong = ratings.get("strong").elo;
+        ratings.update(&[("strong", 100), ("weak", 200)]);
+        let upset = strong - ratings.get("strong").elo;
+
+        ratings.update(&[("strong", 100), ("weak", 50)]);
+        let expected = ratings.get("strong").elo - (strong - upset);
+
+        assert!(upset > expected);
+    }
+
+    #[test]
+    fn multiplayer_games_conserve_rating() {
+        let mut ratings = Ratings::default();
+        ratings.update(&[("a", 10), ("b", 20), ("c", 20), ("d", 5)]);
+
+        let total: f64 = ["a", "b", "c", "d"].iter().map(|name| ratings.get(name).elo).sum();
+        assert!((total - 4.0 * INITIAL_RATING).abs() < 1e-9);
+
+        // Tied snakes should be rated the same
+        assert_eq!(ratings.get("b").elo, ratings.get("c").elo);
+    }
+
+}
@@ -0,0 +1,146 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::snake::api::{SnakeConfig, DEFAULT_TIMEOUT};
+use crate::snake::utils::Move;
+use super::Player;
+
+/// Reply to a move request. Only the move matters to us.
+#[derive(Deserialize)]
+struct MoveReply {
+    r#move: String,
+}
+
+/// Plays a snake running on a server somewhere, over HTTP. HTTPS isn't supported.
+///
+/// Requests are sent in API version 1, so boards are flipped over on the way out. Moves
+/// need no flipping on the way back, since up means towards the top of the board either
+/// way.
+pub struct RemotePlayer {
+    /// URL of the snake, which the endpoint paths are appended to.
+    url: String,
+}
+
+impl RemotePlayer {
+
+    pub fn new(url: &str) -> RemotePlayer {
+        RemotePlayer {
+            url: String::from(url.trim_end_matches('/')),
+        }
+    }
+
+    /// Sends a request to an endpoint, giving up once the game's timeout has passed.
+    /// Returns the body of the response, or None if there wasn't a successful one in time.
+    fn post(&self, endpoint: &str, request: &SnakeConfig) -> Option<String> {
+        let mut request = request.clone();
+        request.flip_vertically();
+        let body = serde_json::to_string(&request).unwrap();
+
+        let response = ureq::post(&format!("{}{}", self.url, endpoint))
+            .set("Content-Type", "application/json")
+            .timeout(Duration::from_millis(request.game.timeout.unwrap_or(DEFAULT_TIMEOUT) as u64))
+            .send_string(&body);
+
+        if !response.ok() {
+            return None;
+        }
+
+        response.into_string().ok()
+    }
+
+}
+
+impl Player for RemotePlayer {
+
+    fn start(&self, request: &SnakeConfig) {
+        // Nothing to do with the response, and the game goes on whether it came or not
+        self.post("/start", request);
+    }
+
+    fn next_move(&self, request: &SnakeConfig) -> Option<Move> {
+        let body = self.post("/move", request)?;
+        let reply: MoveReply = serde_json::from_str(&body).ok()?;
+        reply.r#move.parse().ok()
+    }
+
+    fn end(&self, request: &SnakeConfig) {
+        self.post("/end", request);
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::snake::api::Coords;
+    use rouille::{Server, Request, Response};
+    use std::sync::mpsc;
+    use std::sync::Mutex;
+    use std::thread;
+
+    /// Starts a snake server on a free port which replies to every request with `handler`.
+    /// Returns its URL.
+    fn serve<F>(handler: F) -> String
+        where F: Fn(&Request) -> Response + Send + Sync + 'static {
+
+        let server = Server::new("127.0.0.1:0", handler).unwrap();
+        let url = format!("http://{}", server.server_addr());
+        thread::spawn(move || server.run());
+        url
+    }
+
+    fn request() -> SnakeConfig {
+        let mut request = SnakeConfig::default();
+        request.game.timeout = Some(200);
+        request
+    }
+
+    #[test]
+    fn reads_move_from_reply() {
+        let url = serve(|_| Response::text(r#"{"move": "left", "shout": "hi"}"#));
+        assert_eq!(RemotePlayer::new(&url).next_move(&request()), Some(Move::Left));
+    }
+
+    #[test]
+    fn speaks_v1_coordinates() {
+        let (sender, receiver) = mpsc::channel();
+        let sender = Mutex::new(sender);
+        let url = serve(move |request| {
+            let request: SnakeConfig = rouille::input::json_input(request).unwrap();
+            sender.lock().unwrap().send(request.you.body[0]).unwrap();
+            Response::text(r#"{"move": "up"}"#)
+        });
+
+        let mut request = request();
+        request.board.height = 11;
+        request.you.body = vec!(Coords { x: 1, y: 2 });
+
+        // Rows count up from the bottom in version 1, but up still heads for the top
+        assert_eq!(RemotePlayer::new(&url).next_move(&request), Some(Move::Up));
+        assert_eq!(receiver.recv().unwrap(), Coords { x: 1, y: 8 });
+    }
+
+    #[test]
+    fn bad_replies_give_no_move() {
+        let url = serve(|_| Response::text(r#"{"move": "sideways"}"#));
+        assert_eq!(RemotePlayer::new(&url).next_move(&request()), None);
+
+        let url = serve(|_| Response::text("not json"));
+        assert_eq!(RemotePlayer::new(&url).next_move(&request()), None);
+
+        let url = serve(|_| Response::text(r#"{"move": "up"}"#).with_status_code(500));
+        assert_eq!(RemotePlayer::new(&url).next_move(&request()), None);
+    }
+
+    #[test]
+    fn slow_replies_give_no_move() {
+        let url = serve(|_| {
+            thread::sleep(Duration::from_millis(500));
+            Response::text(r#"{"move": "up"}"#)
+        });
+        assert_eq!(RemotePlayer::new(&url).next_move(&request()), None);
+    }
+
+}
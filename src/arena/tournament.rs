@@ -0,0 +1,249 @@
+//
+// Tournaments: many seeded games between a pool of snakes, with statistics on how each
+// snake did.
+//
+
+use std::str::FromStr;
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use super::rating::Ratings;
+use super::{play_game, Competitor, Settings};
+
+/// z-score of a 95% confidence interval.
+const Z_95: f64 = 1.96;
+
+/// Who plays who.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Format {
+    /// Every pair of snakes plays head-to-head games.
+    RoundRobin,
+    /// Every snake plays in every game.
+    FreeForAll,
+}
+
+impl FromStr for Format {
+
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Format, String> {
+        match name {
+            "round-robin" => Ok(Format::RoundRobin),
+            "free-for-all" => Ok(Format::FreeForAll),
+            _ => Err(format!("Unknown tournament format '{}'", name)),
+        }
+    }
+
+}
+
+/// How a snake did over a tournament.
+#[derive(Debug, Clone)]
+pub struct Standing {
+    pub name: String,
+    pub games: u32,
+    pub wins: u32,
+    /// Turns survived in each game played.
+    pub survival: Vec<u32>,
+}
+
+impl Standing {
+
+    fn new(name: &str) -> Standing {
+        Standing {
+            name: String::from(name),
+            games: 0,
+            wins: 0,
+            survival: Vec::new(),
+        }
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        if self.games == 0 {
+            return 0.0;
+        }
+        self.wins as f64 / self.games as f64
+    }
+
+    /// 95% confidence interval of the win rate (Wilson score interval), which behaves
+    /// sensibly for small numbers of games and win rates close to 0 or 1.
+    pub fn win_rate_interval(&self) -> (f64, f64) {
+        if self.games == 0 {
+            return (0.0, 1.0);
+        }
+
+        let n = self.games as f64;
+        let p = self.win_rate();
+        let z2 = Z_95 * Z_95;
+
+        let center = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+        let margin = Z_95 / (1.0 + z2 / n) * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt();
+
+        ((center - margin).max(0.0), (center + margin).min(1.0))
+    }
+
+    pub fn mean_survival(&self) -> f64 {
+        if self.survival.is_empty() {
+            return 0.0;
+        }
+        self.survival.iter().sum::<u32>() as f64 / self.survival.len() as f64
+    }
+
+    /// Half the width of the 95% confidence interval of the mean survival time.
+    pub fn survival_margin(&self) -> f64 {
+        let n = self.survival.len() as f64;
+        if n < 2.0 {
+            return f64::INFINITY;
+        }
+
+        let mean = self.mean_survival();
+        let variance = self.survival.iter()
+            .map(|&turns| (turns as f64 - mean).powi(2))
+            .sum::<f64>() / (n - 1.0);
+
+        Z_95 * (variance / n).sqrt()
+    }
+
+}
+
+/// Lists the games played in a tournament between `count` snakes, as the indices of the
+/// snakes in each game.
+pub fn schedule(count: usize, format: Format, games: u32) -> Vec<Vec<usize>> {
+    let mut schedule = Vec::new();
+
+    match format {
+        Format::RoundRobin => {
+            for first in 0..count {
+                for second in first + 1..count {
+                    for _ in 0..games {
+                        schedule.push(vec!(first, second));
+                    }
+                }
+            }
+        },
+        Format::FreeForAll => {
+            for _ in 0..games {
+                schedule.push((0..count).collect());
+            }
+        },
+    }
+
+    schedule
+}
+
+/// Plays a tournament, updating `ratings` after every game. Games use consecutive seeds,
+/// starting from `first_seed` and wrapping around to zero after `u64::MAX`, so
+/// tournaments can be replayed.
+///
+/// In a round robin, `games` is the number of games played by each pair of snakes.
+/// Otherwise it is the total number of games played.
+pub fn run_tournament(competitors: &[Competitor], format: Format, games: u32, settings: &Settings,
+                      first_seed: u64, ratings: &mut Ratings) -> Vec<Standing> {
+
+    let mut standings: Vec<Standing> = competitors.iter()
+        .map(|competitor| Standing::new(&competitor.name))
+        .collect();
+
+    for (game, seats) in schedule(competitors.len(), format, games).iter().enumerate() {
+        let players: Vec<Competitor> = seats.iter().map(|&idx| competitors[idx].clone()).collect();
+        let seed = first_seed.wrapping_add(game as u64);
+        let result = play_game(&players, settings, &mut StdRng::seed_from_u64(seed));
+
+        let mut results = Vec::with_capacity(seats.len());
+        for (seat, &idx) in seats.iter().enumerate() {
+            let standing = &mut standings[idx];
+            let survived = result.survival(seat);
+
+            standing.games += 1;
+            standing.survival.push(survived);
+            if result.winner == Some(seat) {
+                standing.wins += 1;
+            }
+
+            // The winner outlasts everyone, including snakes eliminated on the last turn
+            let lasted = if result.winner == Some(seat) { survived + 1 } else { survived };
+            results.push((competitors[idx].name.as_str(), lasted));
+        }
+
+        ratings.update(&results);
+    }
+
+    standings
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::arena::LocalPlayer;
+    use crate::snake::strategy::TailChaser;
+    use std::sync::Arc;
+
+    #[test]
+    fn schedules_every_pair_in_round_robin() {
+        let games = schedule(4, Format::RoundRobin, 3);
+        assert_eq!(games.len(), 6 * 3);
+        assert!(games.iter().all(|game| game.len() == 2 && game[0] < game[1]));
+
+        let games = schedule(4, Format::FreeForAll, 3);
+        assert_eq!(games, vec!(vec!(0, 1, 2, 3); 3));
+    }
+
+    #[test]
+    fn win_rate_interval_contains_win_rate() {
+        let mut standing = Standing::new("snek");
+        standing.games = 20;
+        standing.wins = 5;
+
+        let (low, high) = standing.win_rate_interval();
+        assert!(low < 0.25 && 0.25 < high);
+        assert!(low > 0.0 && high < 1.0);
+
+        // Never winning still leaves room for doubt
+        standing.wins = 0;
+        let (low, high) = standing.win_rate_interval();
+        assert_eq!(low, 0.0);
+        assert!(high > 0.0);
+    }
+
+    #[test]
+    fn survival_margin_shrinks_with_more_games() {
+        let mut standing = Standing::new("snek");
+        standing.survival = vec!(10, 20, 30, 40);
+        let few = standing.survival_margin();
+
+        standing.survival = [10, 20, 30, 40].iter().cycle().take(40).copied().collect();
+        let many = standing.survival_margin();
+
+        assert_eq!(standing.mean_survival(), 25.0);
+        assert!(many < few);
+    }
+
+    #[test]
+    fn tracks_standings_and_ratings() {
+        let competitors: Vec<Competitor> = ["a", "b", "c"].iter().map(|name| Competitor {
+            name: String::from(*name),
+            player: Arc::new(LocalPlayer::new(Box::new(TailChaser))),
+        }).collect();
+
+        let mut ratings = Ratings::default();
+        let standings = run_tournament(&competitors, Format::RoundRobin, 2, &Default::default(), 0, &mut ratings);
+
+        for standing in standings.iter() {
+            assert_eq!(standing.games, 4);
+            assert_eq!(standing.survival.len(), 4);
+            assert_eq!(ratings.get(&standing.name).games, 4);
+        }
+
+        // Seeds carry on from zero after the last one
+        let standings = run_tournament(&competitors[..2], Format::FreeForAll, 2, &Default::default(),
+                                       u64::MAX, &mut ratings);
+        assert_eq!(standings[0].games, 2);
+
+        // A round robin needs a pair of snakes to play at all
+        let standings = run_tournament(&competitors[..1], Format::RoundRobin, 2, &Default::default(),
+                                       u64::MAX, &mut ratings);
+        assert_eq!(standings[0].games, 0);
+    }
+
+}
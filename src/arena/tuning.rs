@@ -0,0 +1,229 @@
+//
+// Tuning of evaluation weights by self-play, with simultaneous perturbation stochastic
+// approximation (SPSA). Each iteration nudges every weight at once in a random direction,
+// plays weights nudged one way against weights nudged the other, and moves the weights
+// towards whichever side did better.
+//
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use rand::Rng;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use serde::{Serialize, Deserialize};
+
+use crate::snake::evaluator::{Weights, FEATURES};
+use crate::snake::strategy::{self, Minimax};
+use super::{play_game, Competitor, LocalPlayer, Settings};
+
+/// How big SPSA's steps are. Steps shrink as tuning goes on, so the weights settle down.
+#[derive(Debug, Clone, Copy)]
+pub struct Spsa {
+    /// Size of the first steps taken, relative to each weight's scale.
+    pub learning_rate: f64,
+
+    /// How far weights are nudged to start with, relative to each weight's scale.
+    pub perturbation: f64,
+
+    /// Number of iterations it takes for steps to start shrinking in earnest. Keeps the
+    /// first few steps from being much bigger than the rest.
+    pub stability: f64,
+}
+
+impl Default for Spsa {
+    fn default() -> Self {
+        Spsa {
+            learning_rate: 0.02,
+            perturbation: 0.1,
+            stability: 10.0,
+        }
+    }
+}
+
+impl Spsa {
+
+    /// Size of the step taken at an iteration.
+    fn step_size(&self, iteration: u32) -> f64 {
+        self.learning_rate / (iteration as f64 + 1.0 + self.stability).powf(0.602)
+    }
+
+    /// How far weights are nudged at an iteration.
+    fn nudge_size(&self, iteration: u32) -> f64 {
+        self.perturbation / (iteration as f64 + 1.0).powf(0.101)
+    }
+
+}
+
+/// Progress of a tuning run, kept in a file so it can be picked up again.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Checkpoint {
+    /// Number of iterations run so far.
+    pub iteration: u32,
+
+    /// Weights the run has got to so far. SPSA never compares them with earlier ones, so
+    /// they're where the search is rather than the best it has seen.
+    pub current: Weights,
+
+    /// Seed of the first iteration's games. Later iterations carry on from it, so a run
+    /// picked up from a checkpoint plays the same games it would have without stopping.
+    pub first_seed: u64,
+
+    /// How much each weight is nudged and stepped by, in the order of `FEATURES`. Taken
+    /// from the starting weights, so small weights aren't swamped by changes to big ones.
+    scales: Vec<f64>,
+
+    /// Score of each iteration's match between nudged weights.
+    pub scores: Vec<f64>,
+}
+
+impl Checkpoint {
+
+    pub fn new(weights: Weights, first_seed: u64) -> Checkpoint {
+        let scales = FEATURES.iter().map(|feature| weights.get(*feature).abs().max(1.0)).collect();
+
+        Checkpoint {
+            iteration: 0,
+            current: weights,
+            first_seed,
+            scales,
+            scores: Vec::new(),
+        }
+    }
+
+    /// Loads a checkpoint from a file, or returns None if the file doesn't exist yet.
+    pub fn load(path: &Path) -> io::Result<Option<Checkpoint>> {
+        match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map(Some)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self).unwrap())
+    }
+
+    /// Seed of the first game of the current iteration, when each iteration plays `games`
+    /// games. Seeds wrap around once they get past the largest one.
+    pub fn seed(&self, games: u32) -> u64 {
+        self.first_seed.wrapping_add((self.iteration as u64).wrapping_mul(games as u64))
+    }
+
+}
+
+/// Runs one iteration of SPSA. `play` is given weights nudged one way and weights nudged
+/// the other, and returns how the first did against the second, from one for always
+/// winning to minus one for always losing. Returns the score of the match.
+pub fn step<R, F>(spsa: &Spsa, checkpoint: &mut Checkpoint, rng: &mut R, play: F) -> f64
+    where R: Rng, F: FnOnce(&Weights, &Weights) -> f64 {
+
+    let nudge_size = spsa.nudge_size(checkpoint.iteration);
+    let step_size = spsa.step_size(checkpoint.iteration);
+
+    let directions: Vec<f64> = FEATURES.iter()
+        .map(|_| if rng.gen::<bool>() { 1.0 } else { -1.0 })
+        .collect();
+
+    let mut plus = checkpoint.current.clone();
+    let mut minus = checkpoint.current.clone();
+    for (idx, feature) in FEATURES.iter().enumerate() {
+        let nudge = nudge_size * checkpoint.scales[idx] * directions[idx];
+        plus.set(*feature, checkpoint.current.get(*feature) + nudge);
+        minus.set(*feature, checkpoint.current.get(*feature) - nudge);
+    }
+
+    let score = play(&plus, &minus);
+
+    // Every weight moves in the direction it was nudged in by the side that did better
+    for (idx, feature) in FEATURES.iter().enumerate() {
+        let gradient = score / (2.0 * nudge_size * directions[idx]);
+        let weight = checkpoint.current.get(*feature) + step_size * gradient * checkpoint.scales[idx];
+        checkpoint.current.set(*feature, weight);
+    }
+
+    checkpoint.iteration += 1;
+    checkpoint.scores.push(score);
+
+    score
+}
+
+/// Plays `games` pairs of games between two sets of weights, with the minimax strategy
+/// set up as in `base` otherwise. The two games of each pair are played from the same
+/// seed, with the snakes swapping places. Games use consecutive seeds, starting from
+/// `first_seed`.
+///
+/// Returns how the first weights did, from one for winning every game to minus one for
+/// losing every game.
+pub fn play_match(first: &Weights, second: &Weights, base: &strategy::Settings, games: u32,
+                  settings: &Settings, first_seed: u64) -> f64 {
+
+    let competitor = |name: &str, weights: &Weights| Competitor {
+        name: String::from(name),
+        player: Arc::new(LocalPlayer::new(Box::new(Minimax::new(&strategy::Settings {
+            weights: weights.clone(),
+            ..base.clone()
+        })))),
+    };
+    let first = competitor("first", first);
+    let second = competitor("second", second);
+
+    let mut score = 0.0;
+    for game in 0..games as u64 {
+        let seed = first_seed.wrapping_add(game);
+        for (seats, first_seat) in [([&first, &second], 0), ([&second, &first], 1)].iter() {
+            let players: Vec<Competitor> = seats.iter().map(|competitor| (*competitor).clone()).collect();
+            let result = play_game(&players, settings, &mut StdRng::seed_from_u64(seed));
+
+            match result.winner {
+                Some(winner) if winner == *first_seat => score += 1.0,
+                Some(_) => score -= 1.0,
+                None => (),
+            }
+        }
+    }
+
+    score / (2 * games.max(1)) as f64
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn steps_towards_better_weights() {
+        // Weights do better the closer their area weight is to 3
+        let fitness = |weights: &Weights| -(weights.area - 3.0).powi(2);
+        let play = |first: &Weights, second: &Weights| (fitness(first) - fitness(second)).clamp(-1.0, 1.0);
+
+        let mut checkpoint = Checkpoint::new(Default::default(), 0);
+        let mut rng = StdRng::seed_from_u64(0);
+        let spsa = Spsa { learning_rate: 0.2, ..Default::default() };
+
+        for _ in 0..200 {
+            step(&spsa, &mut checkpoint, &mut rng, play);
+        }
+
+        assert_eq!(checkpoint.iteration, 200);
+        assert_eq!(checkpoint.scores.len(), 200);
+        assert!((checkpoint.current.area - 3.0).abs() < 0.5, "area weight {}", checkpoint.current.area);
+    }
+
+    #[test]
+    fn iterations_play_different_games() {
+        let mut checkpoint = Checkpoint::new(Default::default(), 100);
+        assert_eq!(checkpoint.seed(4), 100);
+
+        checkpoint.iteration = 3;
+        assert_eq!(checkpoint.seed(4), 112);
+
+        let checkpoint = Checkpoint { iteration: 1, ..Checkpoint::new(Default::default(), u64::MAX) };
+        assert_eq!(checkpoint.seed(4), 3);
+    }
+
+}
@@ -0,0 +1,250 @@
+//
+// Plays games between strategies and snake servers locally, and reports how they went.
+//
+
+use std::fs;
+use std::path::Path;
+use std::process;
+use std::sync::Arc;
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use battlesnake::arena::rating::Ratings;
+use battlesnake::arena::tournament::{run_tournament, schedule, Format};
+use battlesnake::arena::tuning::{self, Checkpoint, Spsa};
+use battlesnake::arena::{play_game, Competitor, LocalPlayer, Player, RemotePlayer, Settings};
+use battlesnake::snake::api::Ruleset;
+use battlesnake::snake::rules::Rules;
+use battlesnake::snake::strategy::{self, STRATEGY_NAMES};
+
+fn exit_with(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+/// Parses the value of an argument, exiting if it's invalid.
+fn parse<T: std::str::FromStr>(matches: &ArgMatches, name: &str) -> T {
+    let value = matches.value_of(name).unwrap();
+    value.parse().unwrap_or_else(|_| exit_with(&format!("Invalid value '{}' for {}", value, name)))
+}
+
+/// Arguments shared by every subcommand.
+fn board_args<'a, 'b>(command: App<'a, 'b>) -> App<'a, 'b> {
+    command
+        .arg(Arg::with_name("width").long("width").takes_value(true).default_value("11"))
+        .arg(Arg::with_name("height").long("height").takes_value(true).default_value("11"))
+        .arg(Arg::with_name("timeout").long("timeout").takes_value(true).default_value("500")
+            .help("Time (in milliseconds) snake servers have to reply before making a default move"))
+        .arg(Arg::with_name("seed").long("seed").takes_value(true)
+            .help("Seed for the first game's random numbers. Picked at random if not given."))
+        .arg(Arg::with_name("ruleset").long("ruleset").takes_value(true).default_value("standard")
+            .possible_values(&["standard", "solo", "royale", "constrictor", "wrapped", "squad"])
+            .help("Game mode to play"))
+        .arg(Arg::with_name("max-turns").long("max-turns").takes_value(true).default_value("1000")
+            .help("Most turns a game goes on for before it's called a draw"))
+}
+
+/// Arguments of subcommands that play games between the snakes given.
+fn game_args<'a, 'b>(command: App<'a, 'b>) -> App<'a, 'b> {
+    board_args(command)
+        .arg(Arg::with_name("games").long("games").short("n").takes_value(true).default_value("1")
+            .help("Number of games to play, with consecutive seeds"))
+        .arg(Arg::with_name("snakes").required(true).multiple(true)
+            .help("Strategies or snake server URLs (http:// only) to play with, one per snake"))
+}
+
+fn settings(matches: &ArgMatches) -> Settings {
+    Settings {
+        width: parse(matches, "width"),
+        height: parse(matches, "height"),
+        timeout: parse(matches, "timeout"),
+        max_turns: parse(matches, "max-turns"),
+        rules: Rules::from_ruleset(&Ruleset {
+            name: String::from(matches.value_of("ruleset").unwrap()),
+            ..Default::default()
+        }),
+    }
+}
+
+fn first_seed(matches: &ArgMatches) -> u64 {
+    match matches.value_of("seed") {
+        Some(_) => parse(matches, "seed"),
+        None => rand::random(),
+    }
+}
+
+fn competitors(matches: &ArgMatches) -> Vec<Competitor> {
+    matches.values_of("snakes").unwrap().map(|name| {
+        let player: Arc<dyn Player> = if name.starts_with("http://") {
+            Arc::new(RemotePlayer::new(name))
+        }
+        else if name.starts_with("https://") {
+            exit_with(&format!("HTTPS isn't supported: {}", name));
+        }
+        else {
+            match strategy::from_name(name, &Default::default()) {
+                Some(strategy) => Arc::new(LocalPlayer::new(strategy)),
+                None => exit_with(&format!(
+                    "Unknown strategy '{}'. Available strategies: {}", name, STRATEGY_NAMES.join(", ")
+                )),
+            }
+        };
+
+        Competitor { name: String::from(name), player }
+    }).collect()
+}
+
+/// Plays games one by one, printing how each of them went.
+fn play(matches: &ArgMatches) {
+    let settings = settings(matches);
+    let competitors = competitors(matches);
+    let first_seed = first_seed(matches);
+    let games: u64 = parse(matches, "games");
+
+    // Several snakes may play the same strategy, so tell them apart by their position
+    let names: Vec<String> = competitors.iter().enumerate()
+        .map(|(idx, competitor)| format!("{}#{}", competitor.name, idx))
+        .collect();
+
+    for game in 0..games {
+        // Seeds picked at random can be anywhere up to u64::MAX, so carry on from zero
+        let seed = first_seed.wrapping_add(game);
+        let result = play_game(&competitors, &settings, &mut StdRng::seed_from_u64(seed));
+
+        match result.winner {
+            Some(winner) => println!("Game {}: {} won after {} turns", seed, names[winner], result.turns),
+            None => println!("Game {}: no winner after {} turns", seed, result.turns),
+        }
+
+        for death in result.deaths.iter() {
+            match death.by {
+                Some(by) => println!("  turn {}: {} {} {}", death.turn, names[death.snake], death.cause, names[by]),
+                None => println!("  turn {}: {} {}", death.turn, names[death.snake], death.cause),
+            }
+        }
+    }
+}
+
+/// Plays a tournament and prints the standings.
+fn tournament(matches: &ArgMatches) {
+    let settings = settings(matches);
+    let competitors = competitors(matches);
+    let first_seed = first_seed(matches);
+    let games: u32 = parse(matches, "games");
+    let format: Format = parse(matches, "format");
+
+    // Ratings are kept by name, so every snake has to have its own
+    for (idx, competitor) in competitors.iter().enumerate() {
+        if competitors[..idx].iter().any(|other| other.name == competitor.name) {
+            exit_with(&format!("{} is in the tournament more than once", competitor.name));
+        }
+    }
+
+    let ratings_path = Path::new(matches.value_of("ratings").unwrap());
+    let mut ratings = Ratings::load(ratings_path)
+        .unwrap_or_else(|err| exit_with(&format!("Couldn't read {}: {}", ratings_path.display(), err)));
+
+    let mut standings = run_tournament(&competitors, format, games, &settings, first_seed, &mut ratings);
+
+    ratings.save(ratings_path)
+        .unwrap_or_else(|err| exit_with(&format!("Couldn't write {}: {}", ratings_path.display(), err)));
+
+    standings.sort_by(|a, b| ratings.get(&b.name).elo.partial_cmp(&ratings.get(&a.name).elo).unwrap());
+
+    match schedule(competitors.len(), format, games).len() as u64 {
+        0 => println!("No games to play"),
+        total_games => println!("{} games, seeds {} to {}",
+            total_games, first_seed, first_seed.wrapping_add(total_games - 1)),
+    }
+    println!("{:<30} {:>6} {:>22} {:>20} {:>7}", "Snake", "Games", "Win rate (95% CI)", "Survival (95% CI)", "Elo");
+
+    for standing in standings.iter() {
+        let (low, high) = standing.win_rate_interval();
+        println!(
+            "{:<30} {:>6} {:>22} {:>20} {:>7.0}",
+            standing.name,
+            standing.games,
+            format!("{:.1}% ({:.1}-{:.1}%)", standing.win_rate() * 100.0, low * 100.0, high * 100.0),
+            format!("{:.1} ± {:.1}", standing.mean_survival(), standing.survival_margin()),
+            ratings.get(&standing.name).elo,
+        );
+    }
+}
+
+/// Tunes evaluation weights with self-play, carrying on from the checkpoint if there is
+/// one, and writes the current weights after every iteration.
+fn tune(matches: &ArgMatches) {
+    let settings = settings(matches);
+    let games: u32 = parse(matches, "games");
+    let iterations: u32 = parse(matches, "iterations");
+
+    let base = strategy::Settings {
+        search_depth: parse(matches, "depth"),
+        ..Default::default()
+    };
+
+    let checkpoint_path = Path::new(matches.value_of("checkpoint").unwrap());
+    let output_path = Path::new(matches.value_of("output").unwrap());
+
+    let mut checkpoint = Checkpoint::load(checkpoint_path)
+        .unwrap_or_else(|err| exit_with(&format!("Couldn't read {}: {}", checkpoint_path.display(), err)))
+        .unwrap_or_else(|| Checkpoint::new(base.weights.clone(), first_seed(matches)));
+
+    let spsa = Spsa::default();
+
+    while checkpoint.iteration < iterations {
+        let seed = checkpoint.seed(games);
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let score = tuning::step(&spsa, &mut checkpoint, &mut rng, |first, second| {
+            tuning::play_match(first, second, &base, games, &settings, seed)
+        });
+        println!("Iteration {}: nudged weights scored {:+.2}", checkpoint.iteration, score);
+
+        checkpoint.save(checkpoint_path)
+            .unwrap_or_else(|err| exit_with(&format!("Couldn't write {}: {}", checkpoint_path.display(), err)));
+        fs::write(output_path, toml::to_string(&checkpoint.current).unwrap())
+            .unwrap_or_else(|err| exit_with(&format!("Couldn't write {}: {}", output_path.display(), err)));
+    }
+
+    println!("Weights after {} iterations:", checkpoint.iteration);
+    print!("{}", toml::to_string(&checkpoint.current).unwrap());
+}
+
+fn main() {
+    let matches = App::new("battlesnake-arena")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Plays games between strategies and snake servers locally")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(game_args(SubCommand::with_name("play")
+            .about("Plays games and prints how each of them went")))
+        .subcommand(game_args(SubCommand::with_name("tournament")
+            .about("Plays a tournament and prints statistics on how each snake did")
+            .arg(Arg::with_name("format").long("format").takes_value(true)
+                .possible_values(&["round-robin", "free-for-all"]).default_value("round-robin")
+                .help("Whether snakes play each other in pairs, or all together"))
+            .arg(Arg::with_name("ratings").long("ratings").takes_value(true).default_value("ratings.json")
+                .help("File Elo ratings are kept in between tournaments"))))
+        .subcommand(board_args(SubCommand::with_name("tune")
+            .about("Tunes the weights minimax evaluates boards with by playing it against itself")
+            .arg(Arg::with_name("iterations").long("iterations").takes_value(true).default_value("100")
+                .help("Number of iterations to tune for, including any already run"))
+            .arg(Arg::with_name("games").long("games").short("n").takes_value(true).default_value("4")
+                .help("Number of pairs of games played each iteration"))
+            .arg(Arg::with_name("depth").long("depth").takes_value(true).default_value("3")
+                .help("Most turns minimax looks ahead"))
+            .arg(Arg::with_name("checkpoint").long("checkpoint").takes_value(true).default_value("tuning.json")
+                .help("File tuning progress is kept in, and picked up again from"))
+            .arg(Arg::with_name("output").long("output").short("o").takes_value(true).default_value("weights.toml")
+                .help("File the current weights are written to after every iteration, for the server's --weights flag"))))
+        .get_matches();
+
+    match matches.subcommand() {
+        ("play", Some(matches)) => play(matches),
+        ("tournament", Some(matches)) => tournament(matches),
+        ("tune", Some(matches)) => tune(matches),
+        _ => unreachable!(),
+    }
+}
@@ -0,0 +1,347 @@
+//
+// Server configuration.
+//
+// Settings are read from (in increasing order of precedence) built-in defaults, a TOML
+// file, environment variables and command line flags.
+//
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::thread;
+
+use clap::{App, Arg, ArgMatches};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+use battlesnake::snake::Appearance;
+use battlesnake::snake::strategy::{Settings, DEFAULT_STRATEGY};
+
+/// Config file read when none is given explicitly, if it exists.
+const DEFAULT_CONFIG_FILE: &str = "battlesnake.toml";
+
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Address to bind the server to.
+    pub address: String,
+    pub port: u16,
+
+    /// Number of worker threads handling requests, or None to let the server decide.
+    pub workers: Option<usize>,
+
+    /// Strategy played by the snake at the root of the server.
+    pub strategy: String,
+
+    /// Appearance of the snake at the root of the server.
+    pub appearance: Appearance,
+
+    /// How the server's strategies play.
+    pub settings: Settings,
+
+    /// Appearance of snakes mounted under their strategy's name, keyed by strategy name.
+    /// Strategies without an entry use the default appearance.
+    pub snakes: HashMap<String, Appearance>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            address: String::from("0.0.0.0"),
+            port: 8080,
+            workers: None,
+            strategy: String::from(DEFAULT_STRATEGY),
+            appearance: Default::default(),
+            settings: Default::default(),
+            snakes: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    /// Config file couldn't be read.
+    Io(String, std::io::Error),
+
+    /// Config file isn't valid.
+    Parse(String, toml::de::Error),
+
+    /// A setting from the environment or command line has a bad value.
+    Invalid(String, String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, err) => write!(f, "Couldn't read {}: {}", path, err),
+            ConfigError::Parse(path, err) => write!(f, "Couldn't parse {}: {}", path, err),
+            ConfigError::Invalid(name, value) => write!(f, "Invalid value '{}' for {}", value, name),
+        }
+    }
+}
+
+/// Command line interface of the server.
+fn cli() -> App<'static, 'static> {
+    let flag = |name: &'static str, help: &'static str| {
+        Arg::with_name(name).long(name).takes_value(true).help(help)
+    };
+
+    App::new("battlesnake")
+        .version(env!("CARGO_PKG_VERSION"))
+        .arg(flag("config", "TOML config file [env: BATTLESNAKE_CONFIG]").short("c"))
+        .arg(flag("address", "Address to bind to [env: BATTLESNAKE_ADDRESS]"))
+        .arg(flag("port", "Port to listen on [env: PORT]").short("p"))
+        .arg(flag("workers", "Number of worker threads [env: BATTLESNAKE_WORKERS]"))
+        .arg(flag("strategy", "Strategy of the root snake [env: BATTLESNAKE_STRATEGY]"))
+        .arg(flag("color", "Color of the root snake [env: BATTLESNAKE_COLOR]"))
+        .arg(flag("head", "Head of the root snake [env: BATTLESNAKE_HEAD]"))
+        .arg(flag("tail", "Tail of the root snake [env: BATTLESNAKE_TAIL]"))
+        .arg(flag("hunger-threshold", "Health below which snakes look for food [env: BATTLESNAKE_HUNGER_THRESHOLD]"))
+        .arg(flag("weights", "TOML file of evaluation weights, as written by battlesnake-arena tune [env: BATTLESNAKE_WEIGHTS]"))
+        .arg(flag("search-threads", "Number of threads searching each move; defaults to the CPU cores, shared between --workers if set [env: BATTLESNAKE_SEARCH_THREADS]"))
+        .arg(flag("search-depth", "Most turns searches look ahead [env: BATTLESNAKE_SEARCH_DEPTH]"))
+        .arg(flag("default-timeout", "Milliseconds to assume the engine waits for a move, if it doesn't say [env: BATTLESNAKE_DEFAULT_TIMEOUT]"))
+        .arg(flag("latency-margin", "Milliseconds kept back from the engine's timeout for the response [env: BATTLESNAKE_LATENCY_MARGIN]"))
+}
+
+fn parse<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, ConfigError> {
+    value.parse().map_err(|_| ConfigError::Invalid(String::from(name), String::from(value)))
+}
+
+fn read_toml<T: DeserializeOwned>(path: &str) -> Result<T, ConfigError> {
+    let contents = fs::read_to_string(path)
+        .map_err(|err| ConfigError::Io(String::from(path), err))?;
+
+    toml::from_str(&contents).map_err(|err| ConfigError::Parse(String::from(path), err))
+}
+
+impl Config {
+
+    /// Loads the config for this process from its command line and environment.
+    ///
+    /// Exits the process if the command line is malformed or help was requested.
+    pub fn load() -> Result<Config, ConfigError> {
+        let matches = cli().get_matches();
+        Config::load_from(&matches, |name| std::env::var(name).ok(), Some(DEFAULT_CONFIG_FILE))
+    }
+
+    /// Loads the config from parsed command line flags and a source of environment
+    /// variables. `default_file` is read if no config file is given and it exists.
+    fn load_from<F>(matches: &ArgMatches, env: F, default_file: Option<&str>) -> Result<Config, ConfigError>
+        where F: Fn(&str) -> Option<String> {
+
+        let path = matches.value_of("config").map(String::from)
+            .or_else(|| env("BATTLESNAKE_CONFIG"));
+
+        let mut config = match path {
+            Some(path) => Config::from_file(&path)?,
+            None => match default_file {
+                Some(file) if Path::new(file).exists() => Config::from_file(file)?,
+                _ => Default::default(),
+            },
+        };
+
+        config.apply(|name, var| {
+            matches.value_of(name).map(String::from).or_else(|| env(var))
+        })?;
+
+        if config.settings.search_threads.is_none() {
+            config.settings.search_threads = Some(config.default_search_threads());
+        }
+
+        Ok(config)
+    }
+
+    pub fn from_file(path: &str) -> Result<Config, ConfigError> {
+        read_toml(path)
+    }
+
+    /// Overrides settings with values from `lookup`, which is given the name of a command
+    /// line flag and its environment variable.
+    fn apply<F>(&mut self, lookup: F) -> Result<(), ConfigError>
+        where F: Fn(&str, &str) -> Option<String> {
+
+        if let Some(address) = lookup("address", "BATTLESNAKE_ADDRESS") {
+            self.address = address;
+        }
+
+        if let Some(port) = lookup("port", "PORT") {
+            self.port = parse("port", &port)?;
+        }
+
+        if let Some(workers) = lookup("workers", "BATTLESNAKE_WORKERS") {
+            self.workers = Some(parse("workers", &workers)?);
+        }
+
+        if let Some(strategy) = lookup("strategy", "BATTLESNAKE_STRATEGY") {
+            self.strategy = strategy;
+        }
+
+        if let Some(color) = lookup("color", "BATTLESNAKE_COLOR") {
+            self.appearance.color = color;
+        }
+
+        if let Some(head) = lookup("head", "BATTLESNAKE_HEAD") {
+            self.appearance.head = head;
+        }
+
+        if let Some(tail) = lookup("tail", "BATTLESNAKE_TAIL") {
+            self.appearance.tail = tail;
+        }
+
+        if let Some(threshold) = lookup("hunger-threshold", "BATTLESNAKE_HUNGER_THRESHOLD") {
+            self.settings.hunger_threshold = parse("hunger-threshold", &threshold)?;
+        }
+
+        if let Some(path) = lookup("weights", "BATTLESNAKE_WEIGHTS") {
+            self.settings.weights = read_toml(&path)?;
+        }
+
+        if let Some(threads) = lookup("search-threads", "BATTLESNAKE_SEARCH_THREADS") {
+            self.settings.search_threads = Some(parse("search-threads", &threads)?);
+        }
+
+        if let Some(depth) = lookup("search-depth", "BATTLESNAKE_SEARCH_DEPTH") {
+            self.settings.search_depth = parse("search-depth", &depth)?;
+        }
+
+        if let Some(timeout) = lookup("default-timeout", "BATTLESNAKE_DEFAULT_TIMEOUT") {
+            self.settings.default_timeout = parse("default-timeout", &timeout)?;
+        }
+
+        if let Some(margin) = lookup("latency-margin", "BATTLESNAKE_LATENCY_MARGIN") {
+            self.settings.latency_margin = parse("latency-margin", &margin)?;
+        }
+
+        Ok(())
+    }
+
+    /// Number of threads each move is searched on, unless set explicitly. A set number of
+    /// workers is taken as the number of moves expected at once, and they share the CPU
+    /// cores between them. Otherwise every move gets every core: the server starts eight
+    /// workers per core when left to decide, but they're mostly idle, and sharing the
+    /// cores between all of them would leave each search on a single thread.
+    fn default_search_threads(&self) -> usize {
+        let cores = thread::available_parallelism().map_or(1, |cores| cores.get());
+
+        match self.workers {
+            Some(workers) => (cores / workers.max(1)).max(1),
+            None => cores,
+        }
+    }
+
+    /// Address and port the server listens on.
+    pub fn bind_address(&self) -> String {
+        format!("{}:{}", self.address, self.port)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use battlesnake::snake::evaluator::Weights;
+    use battlesnake::snake::strategy::Rollout;
+
+    #[test]
+    fn parses_config_file() {
+        let config: Config = toml::from_str(r##"
+            port = 9000
+            workers = 4
+            strategy = "tailchaser"
+
+            [appearance]
+            color = "#00FF00"
+
+            [settings]
+            hunger_threshold = 25
+            mcts_rollout = "random"
+
+            [settings.weights]
+            territory = 0.5
+
+            [snakes.tailchaser]
+            head = "silly"
+        "##).unwrap();
+
+        assert_eq!(config.bind_address(), "0.0.0.0:9000");
+        assert_eq!(config.workers, Some(4));
+        assert_eq!(config.appearance.color, "#00FF00");
+        assert_eq!(config.settings.hunger_threshold, 25);
+        assert_eq!(config.settings.mcts_rollout, Rollout::Random);
+        assert_eq!(config.settings.weights.territory, 0.5);
+        assert_eq!(config.settings.weights.length, 5.0);
+
+        // Missing fields should fall back to defaults
+        assert_eq!(config.appearance.head, "beluga");
+        assert_eq!(config.snakes["tailchaser"].head, "silly");
+        assert_eq!(config.snakes["tailchaser"].color, "#FF0000");
+    }
+
+    #[test]
+    fn command_line_overrides_environment() {
+        let matches = cli().get_matches_from(vec!(
+            "battlesnake", "--port", "3000", "--color", "#0000FF", "--latency-margin", "50"));
+        let env = |name: &str| match name {
+            "PORT" => Some(String::from("5000")),
+            "BATTLESNAKE_WORKERS" => Some(String::from("2")),
+            "BATTLESNAKE_SEARCH_THREADS" => Some(String::from("3")),
+            "BATTLESNAKE_SEARCH_DEPTH" => Some(String::from("6")),
+            "BATTLESNAKE_DEFAULT_TIMEOUT" => Some(String::from("800")),
+            "BATTLESNAKE_LATENCY_MARGIN" => Some(String::from("100")),
+            "BATTLESNAKE_COLOR" => Some(String::from("#00FF00")),
+            _ => None,
+        };
+
+        let config = Config::load_from(&matches, env, None).unwrap();
+        assert_eq!(config.port, 3000);
+        assert_eq!(config.workers, Some(2));
+        assert_eq!(config.settings.search_threads, Some(3));
+        assert_eq!(config.settings.search_depth, 6);
+        assert_eq!(config.settings.default_timeout, 800);
+        assert_eq!(config.settings.latency_margin, 50);
+        assert_eq!(config.appearance.color, "#0000FF");
+    }
+
+    #[test]
+    fn shares_cores_between_workers() {
+        let cores = thread::available_parallelism().map_or(1, |cores| cores.get());
+
+        let matches = cli().get_matches_from(vec!("battlesnake"));
+        let config = Config::load_from(&matches, |_| None, None).unwrap();
+        assert_eq!(config.settings.search_threads, Some(cores));
+
+        let matches = cli().get_matches_from(vec!("battlesnake", "--workers", "2"));
+        let config = Config::load_from(&matches, |_| None, None).unwrap();
+        assert_eq!(config.settings.search_threads, Some((cores / 2).max(1)));
+    }
+
+    #[test]
+    fn loads_weights_file() {
+        // Written the same way as by the tuner
+        let weights = Weights { area: 2.0, ..Default::default() };
+        let name = format!("battlesnake-config-test-weights-{}.toml", std::process::id());
+        let path = std::env::temp_dir().join(name);
+        fs::write(&path, toml::to_string(&weights).unwrap()).unwrap();
+
+        let matches = cli().get_matches_from(vec!("battlesnake", "--weights", path.to_str().unwrap()));
+        let config = Config::load_from(&matches, |_| None, None).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(config.settings.weights, weights);
+    }
+
+    #[test]
+    fn rejects_bad_values() {
+        let matches = cli().get_matches_from(vec!("battlesnake"));
+        let env = |name: &str| match name {
+            "PORT" => Some(String::from("eighty")),
+            _ => None,
+        };
+
+        assert!(Config::load_from(&matches, env, None).is_err());
+    }
+
+}
@@ -1,26 +1,292 @@
 use rouille::Response;
 use rouille::Request;
 use rouille::try_or_400;
+use std::process;
+use std::time::Instant;
 
-mod snake;
+mod config;
+mod registry;
 
-use snake::api::SnakeConfig;
-use snake::{handle_start, handle_move, handle_end};
+use battlesnake::snake::api::{SnakeConfig, ApiVersion};
+use battlesnake::snake::strategy::{self, STRATEGY_NAMES};
+use battlesnake::snake::{handle_info, handle_start, handle_move, handle_end};
+use config::Config;
+use registry::{Registry, MountedSnake};
 
-fn handle_request(request: &Request) -> Response {
+/// Prefix under which the snake speaks API version 1. Everything else speaks API version
+/// 2020.01, so bots already pointed at the server carry on as before.
+const V1_PREFIX: &str = "/v1";
 
-    let snake_config: SnakeConfig = try_or_400!(rouille::input::json_input(request));
+/// Endpoints exposed by the snake server.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Endpoint {
+    Info,
+    Ping,
+    Start,
+    Move,
+    End,
+}
+
+/// Works out which endpoint a path refers to and which HTTP method it accepts, or None if
+/// the path doesn't exist.
+fn route(version: ApiVersion, path: &str) -> Option<(&'static str, Endpoint)> {
+    match (version, path) {
+        (_, "") | (_, "/") => Some(("GET", Endpoint::Info)),
+        (ApiVersion::V2020_01, "/ping") => Some(("POST", Endpoint::Ping)),
+        (_, "/start") => Some(("POST", Endpoint::Start)),
+        (_, "/move") => Some(("POST", Endpoint::Move)),
+        (_, "/end") => Some(("POST", Endpoint::End)),
+        _ => None,
+    }
+}
+
+fn handle_request(registry: &Registry, request: &Request) -> Response {
+
+    // The engine's timeout runs from when it sent the request, so start the clock as
+    // early as we can
+    let received = Instant::now();
+
+    let url = request.url();
+    let (snake, url) = match registry.resolve(&url) {
+        Some(resolved) => resolved,
+        None => return Response::empty_404(),
+    };
+
+    let (version, path) = match url.strip_prefix(V1_PREFIX) {
+        Some(path) => (ApiVersion::V1, path),
+        None => (ApiVersion::V2020_01, url),
+    };
+
+    // Route before touching the body, since only the game endpoints have one
+    let (method, endpoint) = match route(version, path) {
+        Some(route) => route,
+        None => return Response::empty_404(),
+    };
+
+    if request.method() != method {
+        return Response::text("Method Not Allowed")
+            .with_status_code(405)
+            .with_additional_header("Allow", method);
+    }
+
+    let strategy = snake.strategy.as_ref();
+    let appearance = &snake.appearance;
+
+    match endpoint {
+        Endpoint::Info => return Response::json(&handle_info(appearance)),
+        Endpoint::Ping => return Response::text(""),
+        _ => {},
+    }
+
+    let mut snake_config: SnakeConfig = try_or_400!(rouille::input::json_input(request));
+    if version == ApiVersion::V1 {
+        snake_config.flip_vertically();
+    }
 
-    match request.url().as_str() {
-        "/start" => Response::json(&handle_start(snake_config)),
-        "/move" =>  Response::json(&handle_move(snake_config)),
-        "/end" =>   Response::json(&handle_end(snake_config)),
-        _ => Response::empty_404(),
+    match (version, endpoint) {
+        (ApiVersion::V2020_01, Endpoint::Start) => Response::json(&handle_start(strategy, appearance, snake_config)),
+        (ApiVersion::V1, Endpoint::Start) => {
+            // Appearance was already sent with the info response, so there's nothing
+            // to reply with.
+            handle_start(strategy, appearance, snake_config);
+            Response::text("")
+        },
+        (_, Endpoint::Move) => Response::json(&handle_move(strategy, snake_config, received)),
+        (_, Endpoint::End) => Response::json(&handle_end(strategy, snake_config)),
+        (_, Endpoint::Info) | (_, Endpoint::Ping) => unreachable!(),
     }
 }
 
 fn main() {
-    rouille::start_server_with_pool("0.0.0.0:8080", None, move|request| {
-        handle_request(request)
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    };
+
+    for name in config.snakes.keys() {
+        if !STRATEGY_NAMES.contains(&name.as_str()) {
+            eprintln!("Unknown snake '{}' in config", name);
+            process::exit(1);
+        }
+    }
+
+    let mut registry = Registry::new();
+
+    // Snake at the root of the server plays the configured strategy
+    let strategy = match strategy::from_name(&config.strategy, &config.settings) {
+        Some(strategy) => strategy,
+        None => {
+            eprintln!("Unknown strategy '{}'", config.strategy);
+            process::exit(1);
+        }
+    };
+
+    registry.mount_root(MountedSnake {
+        strategy,
+        appearance: config.appearance.clone(),
+    });
+
+    // Every strategy is also available under its own name, so several of them can be
+    // entered into the same game
+    for name in STRATEGY_NAMES {
+        registry.mount(name, MountedSnake {
+            strategy: strategy::from_name(name, &config.settings).unwrap(),
+            appearance: config.snakes.get(*name).cloned().unwrap_or_default(),
+        });
+    }
+
+    rouille::start_server_with_pool(config.bind_address(), config.workers, move|request| {
+        handle_request(&registry, request)
     })
 }
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::Read;
+    use std::sync::Arc;
+    use std::thread;
+
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::*;
+    use battlesnake::arena::{self, Competitor, RemotePlayer};
+    use battlesnake::snake::rules::EliminationCause;
+    use battlesnake::snake::strategy::TailChaser;
+
+    fn registry() -> Registry {
+        let mut registry = Registry::new();
+        registry.mount_root(MountedSnake {
+            strategy: Box::new(TailChaser),
+            appearance: Default::default(),
+        });
+        registry.mount("tailchaser", MountedSnake {
+            strategy: Box::new(TailChaser),
+            appearance: Default::default(),
+        });
+        registry
+    }
+
+    #[test]
+    fn get_info_does_not_need_a_body() {
+        let request = Request::fake_http("GET", "/", vec!(), vec!());
+        assert_eq!(handle_request(&registry(), &request).status_code, 200);
+
+        let request = Request::fake_http("GET", "/v1/", vec!(), vec!());
+        assert_eq!(handle_request(&registry(), &request).status_code, 200);
+
+        let request = Request::fake_http("GET", "/tailchaser/", vec!(), vec!());
+        assert_eq!(handle_request(&registry(), &request).status_code, 200);
+    }
+
+    #[test]
+    fn wrong_method_is_not_allowed() {
+        let request = Request::fake_http("GET", "/move", vec!(), vec!());
+        assert_eq!(handle_request(&registry(), &request).status_code, 405);
+
+        let request = Request::fake_http("POST", "/", vec!(), vec!());
+        assert_eq!(handle_request(&registry(), &request).status_code, 405);
+    }
+
+    #[test]
+    fn unknown_path_is_not_found() {
+        let request = Request::fake_http("POST", "/nope", vec!(), vec!());
+        assert_eq!(handle_request(&registry(), &request).status_code, 404);
+
+        // Ping only exists in API version 2020.01
+        let request = Request::fake_http("POST", "/v1/ping", vec!(), vec!());
+        assert_eq!(handle_request(&registry(), &request).status_code, 404);
+    }
+
+    #[test]
+    fn game_endpoints_require_a_body() {
+        let request = Request::fake_http("POST", "/move", vec!(), vec!());
+        assert_eq!(handle_request(&registry(), &request).status_code, 400);
+    }
+
+    /// Sends a move request, and returns the move the snake made.
+    fn request_move(path: &str, body: String) -> String {
+        let headers = vec!((String::from("Content-Type"), String::from("application/json")));
+        let request = Request::fake_http("POST", path, headers, body.into_bytes());
+        let response = handle_request(&registry(), &request);
+        assert_eq!(response.status_code, 200);
+
+        let (mut reader, _) = response.data.into_reader_and_size();
+        let mut reply = String::new();
+        reader.read_to_string(&mut reply).unwrap();
+        reply
+    }
+
+    /// Move request for a 3x3 board with our snake across the middle row, and an enemy
+    /// across the first row. `head` is added to each snake's fields.
+    fn middle_row_request(head: &str) -> String {
+        let snake = format!(r#"{{
+            "id": "you", "name": "You", "health": 90,
+            "body": [{{ "x": 0, "y": 1 }}, {{ "x": 1, "y": 1 }}, {{ "x": 2, "y": 1 }}, {{ "x": 2, "y": 2 }}]
+            {}
+        }}"#, head);
+        let enemy = r#"{
+            "id": "enemy", "name": "Enemy", "health": 90,
+            "body": [{ "x": 0, "y": 0 }, { "x": 1, "y": 0 }, { "x": 2, "y": 0 }]
+        }"#;
+
+        format!(r#"{{
+            "game": {{ "id": "game-id" }},
+            "turn": 5,
+            "board": {{ "height": 3, "width": 3, "food": [], "snakes": [{}, {}] }},
+            "you": {}
+        }}"#, snake, enemy, snake)
+    }
+
+    #[test]
+    fn v1_rows_count_up_from_the_bottom() {
+        // Board state, with y counting up from the bottom as in API version 1:
+        //   - - S
+        //   Y S S
+        //   E E E
+        //
+        // The enemy's head is right below ours, so up is the only way out, and the only
+        // way round to our tail.
+        let reply = request_move("/v1/move", middle_row_request(r#", "head": { "x": 0, "y": 1 }"#));
+        assert!(reply.contains(r#""move":"up""#), "{}", reply);
+    }
+
+    #[test]
+    fn v2020_01_rows_count_down_from_the_top() {
+        // Board state, with y counting down from the top as in API version 2020.01:
+        //   E E E
+        //   Y S S
+        //   - - S
+        //
+        // The same request as for API version 1 (bar the head), sent to the path
+        // 2020.01 engines have always used. This time the way out is down.
+        let reply = request_move("/move", middle_row_request(""));
+        assert!(reply.contains(r#""move":"down""#), "{}", reply);
+    }
+
+
+    #[test]
+    fn arena_plays_the_server_over_v1() {
+        let registry = registry();
+        let server = rouille::Server::new("127.0.0.1:0", move |request| handle_request(&registry, request)).unwrap();
+        let url = format!("http://{}/v1", server.server_addr());
+        thread::spawn(move || server.run());
+
+        let player = Arc::new(RemotePlayer::new(&url));
+        let competitors: Vec<Competitor> = (0..2)
+            .map(|idx| Competitor { name: format!("remote-{}", idx), player: player.clone() })
+            .collect();
+
+        // Tail chasers never go near the walls, unless their moves get turned around
+        let settings = arena::Settings { max_turns: 50, ..Default::default() };
+        for seed in 1..=3 {
+            let result = arena::play_game(&competitors, &settings, &mut StdRng::seed_from_u64(seed));
+            assert!(result.deaths.iter().all(|death| death.cause != EliminationCause::OutOfBounds), "{:?}", result);
+        }
+    }
+
+}
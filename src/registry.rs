@@ -0,0 +1,113 @@
+//
+// Registry of the snakes served by this process.
+//
+
+use std::collections::HashMap;
+
+use battlesnake::snake::Appearance;
+use battlesnake::snake::strategy::Strategy;
+
+/// A snake mounted on the server.
+pub struct MountedSnake {
+    pub strategy: Box<dyn Strategy>,
+    pub appearance: Appearance,
+}
+
+/// Maps URL prefixes to snakes. A snake mounted at `hungry` answers `/hungry/move`,
+/// `/hungry/v1/move` and so on. A snake mounted at the root answers any request that
+/// isn't claimed by a named snake.
+#[derive(Default)]
+pub struct Registry {
+    root: Option<MountedSnake>,
+    named: HashMap<String, MountedSnake>,
+}
+
+impl Registry {
+
+    pub fn new() -> Registry {
+        Default::default()
+    }
+
+    /// Mounts a snake at the root of the server, replacing any snake already there.
+    pub fn mount_root(&mut self, snake: MountedSnake) {
+        self.root = Some(snake);
+    }
+
+    /// Mounts a snake under a prefix, replacing any snake already there. Prefixes are a
+    /// single path segment, without slashes.
+    pub fn mount(&mut self, prefix: &str, snake: MountedSnake) {
+        assert!(!prefix.is_empty() && !prefix.contains('/'));
+        self.named.insert(String::from(prefix), snake);
+    }
+
+    /// Finds the snake that should handle a URL. Returns the snake along with the rest of
+    /// the URL once its prefix has been stripped, or None if no snake claims the URL.
+    pub fn resolve<'a>(&self, url: &'a str) -> Option<(&MountedSnake, &'a str)> {
+        let path = url.strip_prefix('/').unwrap_or(url);
+        let segment_len = path.find('/').unwrap_or(path.len());
+
+        if let Some(snake) = self.named.get(&path[..segment_len]) {
+            return Some((snake, &path[segment_len..]));
+        }
+
+        self.root.as_ref().map(|snake| (snake, url))
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use battlesnake::snake::strategy::TailChaser;
+
+    fn snake(color: &str) -> MountedSnake {
+        MountedSnake {
+            strategy: Box::new(TailChaser),
+            appearance: Appearance {
+                color: String::from(color),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn resolves_named_snakes() {
+        let mut registry = Registry::new();
+        registry.mount("one", snake("#000001"));
+        registry.mount("two", snake("#000002"));
+
+        let (snake, rest) = registry.resolve("/one/move").unwrap();
+        assert_eq!(snake.appearance.color, "#000001");
+        assert_eq!(rest, "/move");
+
+        let (snake, rest) = registry.resolve("/two/v1/start").unwrap();
+        assert_eq!(snake.appearance.color, "#000002");
+        assert_eq!(rest, "/v1/start");
+
+        let (_, rest) = registry.resolve("/two").unwrap();
+        assert_eq!(rest, "");
+    }
+
+    #[test]
+    fn falls_back_to_root_snake() {
+        let mut registry = Registry::new();
+        registry.mount("one", snake("#000001"));
+
+        // Without a root snake, unclaimed URLs go nowhere
+        assert!(registry.resolve("/move").is_none());
+        assert!(registry.resolve("/oneandahalf/move").is_none());
+
+        registry.mount_root(snake("#000000"));
+
+        let (snake, rest) = registry.resolve("/move").unwrap();
+        assert_eq!(snake.appearance.color, "#000000");
+        assert_eq!(rest, "/move");
+
+        // Prefixes only match whole path segments
+        let (snake, rest) = registry.resolve("/oneandahalf/move").unwrap();
+        assert_eq!(snake.appearance.color, "#000000");
+        assert_eq!(rest, "/oneandahalf/move");
+    }
+
+}
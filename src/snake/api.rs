@@ -1,58 +1,206 @@
-// Structues for Battlesnake API version 2020.01
-// See https://docs.battlesnake.com/snake-api
+// Structures for the Battlesnake API.
+//
+// Requests are parsed into a single set of structures covering both API version 2020.01
+// and API version 1. Fields that are only sent by version 1 fall back to their defaults
+// when a 2020.01 engine talks to us.
+//
+// Coordinates count rows down from the top of the board, as in API version 2020.01.
+// Version 1 counts them up from the bottom instead, so its requests are flipped over
+// with `SnakeConfig::flip_vertically` as they come in.
+//
+// See https://docs.battlesnake.com/references/api
 
 use serde::Serialize;
 use serde::Deserialize;
 use std::default::Default;
 
+/// Versions of the Battlesnake API we know how to speak.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ApiVersion {
+    V2020_01,
+    V1,
+}
+
 //
 // Game structures
 //
 
-#[derive(Default, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Ruleset {
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+
+    /// How the rules are set up. Only sent by later releases of API version 1.
+    #[serde(default)]
+    pub settings: RulesetSettings,
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Ruleset {
+            name: String::from("standard"),
+            version: String::new(),
+            settings: Default::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RulesetSettings {
+    /// Percent chance of a new piece of food spawning each turn.
+    pub food_spawn_chance: u32,
+    pub minimum_food: u32,
+
+    /// Health lost each turn by snakes with their heads in a hazard, on top of the usual
+    /// one.
+    pub hazard_damage_per_turn: u32,
+
+    pub royale: RoyaleSettings,
+    pub squad: SquadSettings,
+}
+
+impl Default for RulesetSettings {
+    fn default() -> Self {
+        RulesetSettings {
+            food_spawn_chance: 15,
+            minimum_food: 1,
+            hazard_damage_per_turn: 14,
+            royale: Default::default(),
+            squad: Default::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RoyaleSettings {
+    /// Number of turns between the hazards closing in.
+    pub shrink_every_n_turns: u32,
+}
+
+impl Default for RoyaleSettings {
+    fn default() -> Self {
+        RoyaleSettings {
+            shrink_every_n_turns: 25,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SquadSettings {
+    /// Whether snakes can move through their squadmates' bodies.
+    pub allow_body_collisions: bool,
+
+    /// Whether a snake being eliminated takes its whole squad with it.
+    pub shared_elimination: bool,
+
+    /// Whether squadmates all have the health of the healthiest of them.
+    pub shared_health: bool,
+
+    /// Whether squadmates all have the length of the longest of them.
+    pub shared_length: bool,
+}
+
+impl Default for SquadSettings {
+    fn default() -> Self {
+        SquadSettings {
+            allow_body_collisions: true,
+            shared_elimination: true,
+            shared_health: true,
+            shared_length: true,
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize, Clone, Debug)]
 pub struct Game {
     pub id: String,
+
+    /// Rules in play. Only sent by API version 1.
+    #[serde(default)]
+    pub ruleset: Ruleset,
+
+    /// Time (in milliseconds) the engine waits for a response. Only sent by API version 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u32>,
 }
 
-#[derive(Deserialize, PartialEq)]
+/// Time (in milliseconds) engines wait for a response unless they say otherwise.
+pub const DEFAULT_TIMEOUT: u32 = 500;
+
+#[derive(Default, Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
 pub struct Coords {
     pub x: u32,
     pub y: u32,
 }
 
-#[derive(Deserialize)]
+#[derive(Default, Serialize, Deserialize, Clone, Debug)]
+pub struct Customizations {
+    pub color: String,
+    pub head: String,
+    pub tail: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Snake {
     pub id: String,
     pub name: String,
     pub health: u8,
     pub body: Vec<Coords>,
+    #[serde(default)]
     pub shout: String,
+
+    // Fields below are only sent by API version 1.
+
+    #[serde(default)]
+    pub head: Coords,
+    #[serde(default)]
+    pub length: u32,
+    /// Latency of the snake's last response in milliseconds, sent as a string.
+    #[serde(default)]
+    pub latency: String,
+    #[serde(default)]
+    pub squad: String,
+    #[serde(default)]
+    pub customizations: Customizations,
 }
 
 // Default snake implementation for unit testing
 impl Default for Snake {
     fn default() -> Self {
         Snake {
-            id: String::from("snake_id"), 
+            id: String::from("snake_id"),
             name: String::from("Snekky Snek"),
             health: 100,
             body: vec!(
                 Coords { x: 0, y: 0 }
             ),
-            shout: String::from("I am snek!")
+            shout: String::from("I am snek!"),
+            head: Coords { x: 0, y: 0 },
+            length: 1,
+            latency: String::from("0"),
+            squad: String::new(),
+            customizations: Default::default(),
         }
     }
 }
 
-#[derive(Default, Deserialize)]
+#[derive(Default, Serialize, Deserialize, Clone, Debug)]
 pub struct Board {
     pub height: u32,
     pub width: u32,
     pub food: Vec<Coords>,
     pub snakes: Vec<Snake>,
+
+    /// Hazard squares. Only sent by API version 1.
+    #[serde(default)]
+    pub hazards: Vec<Coords>,
 }
 
-#[derive(Default, Deserialize)]
+#[derive(Default, Serialize, Deserialize, Clone, Debug)]
 pub struct SnakeConfig {
     pub game: Game,
     pub turn: u32,
@@ -60,16 +208,48 @@ pub struct SnakeConfig {
     pub you: Snake,
 }
 
+impl SnakeConfig {
+
+    /// Mirrors the board top to bottom, turning API version 1 coordinates into the ones
+    /// we use and back again.
+    pub fn flip_vertically(&mut self) {
+        let height = self.board.height;
+        let flip = |coords: &mut Coords| coords.y = height.saturating_sub(coords.y + 1);
+
+        self.board.food.iter_mut().for_each(flip);
+        self.board.hazards.iter_mut().for_each(flip);
+
+        for snake in self.board.snakes.iter_mut().chain(std::iter::once(&mut self.you)) {
+            snake.body.iter_mut().for_each(flip);
+            flip(&mut snake.head);
+        }
+    }
+
+}
+
 //
 // Response types
 //
 
+/// Response to `GET /` (API version 1 only). Describes the snake's appearance.
+#[derive(Serialize)]
+pub struct InfoResponse<'a> {
+    pub apiversion: &'a str,
+    pub author: &'a str,
+    pub color: &'a str,
+    pub head: &'a str,
+    pub tail: &'a str,
+    pub version: &'a str,
+}
+
+/// Response to `POST /start` (API version 2020.01 only). Version 1 engines ignore the
+/// response body and read the snake's appearance from `InfoResponse` instead.
 #[derive(Serialize)]
 #[allow(non_snake_case)]
-pub struct StartResponse {
-    pub color: &'static str,
-    pub headType: &'static str,
-    pub tailType: &'static str,
+pub struct StartResponse<'a> {
+    pub color: &'a str,
+    pub headType: &'a str,
+    pub tailType: &'a str,
 }
 
 #[derive(Serialize)]
@@ -77,3 +257,108 @@ pub struct MoveResponse {
     pub r#move: &'static str,
     pub shout: &'static str,
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parses_2020_01_request() {
+        let config: SnakeConfig = serde_json::from_str(r#"{
+            "game": { "id": "game-id" },
+            "turn": 4,
+            "board": {
+                "height": 11,
+                "width": 11,
+                "food": [{ "x": 5, "y": 5 }],
+                "snakes": [{
+                    "id": "snake-id",
+                    "name": "Snek",
+                    "health": 90,
+                    "body": [{ "x": 1, "y": 1 }, { "x": 1, "y": 2 }],
+                    "shout": ""
+                }]
+            },
+            "you": {
+                "id": "snake-id",
+                "name": "Snek",
+                "health": 90,
+                "body": [{ "x": 1, "y": 1 }, { "x": 1, "y": 2 }],
+                "shout": ""
+            }
+        }"#).unwrap();
+
+        assert_eq!(config.turn, 4);
+        assert_eq!(config.you.body.len(), 2);
+
+        // Version 1 fields should fall back to defaults
+        assert_eq!(config.game.ruleset.name, "standard");
+        assert_eq!(config.game.ruleset.settings, RulesetSettings::default());
+        assert_eq!(config.game.timeout, None);
+        assert!(config.board.hazards.is_empty());
+    }
+
+    #[test]
+    fn parses_v1_request() {
+        let config: SnakeConfig = serde_json::from_str(r##"{
+            "game": {
+                "id": "game-id",
+                "ruleset": {
+                    "name": "royale",
+                    "version": "v1.0.17",
+                    "settings": {
+                        "foodSpawnChance": 25,
+                        "minimumFood": 1,
+                        "hazardDamagePerTurn": 15,
+                        "royale": { "shrinkEveryNTurns": 10 }
+                    }
+                },
+                "timeout": 300
+            },
+            "turn": 14,
+            "board": {
+                "height": 11,
+                "width": 11,
+                "food": [{ "x": 5, "y": 5 }],
+                "hazards": [{ "x": 0, "y": 0 }],
+                "snakes": [{
+                    "id": "snake-id",
+                    "name": "Snek",
+                    "health": 54,
+                    "body": [{ "x": 0, "y": 1 }, { "x": 1, "y": 1 }, { "x": 2, "y": 1 }],
+                    "latency": "111",
+                    "head": { "x": 0, "y": 1 },
+                    "length": 3,
+                    "shout": "why are we shouting??",
+                    "squad": "",
+                    "customizations": { "color": "#FF0000", "head": "pixel", "tail": "pixel" }
+                }]
+            },
+            "you": {
+                "id": "snake-id",
+                "name": "Snek",
+                "health": 54,
+                "body": [{ "x": 0, "y": 1 }, { "x": 1, "y": 1 }, { "x": 2, "y": 1 }],
+                "latency": "111",
+                "head": { "x": 0, "y": 1 },
+                "length": 3,
+                "shout": "why are we shouting??",
+                "squad": "",
+                "customizations": { "color": "#FF0000", "head": "pixel", "tail": "pixel" }
+            }
+        }"##).unwrap();
+
+        assert_eq!(config.game.ruleset.name, "royale");
+        assert_eq!(config.game.ruleset.settings.food_spawn_chance, 25);
+        assert_eq!(config.game.ruleset.settings.hazard_damage_per_turn, 15);
+        assert_eq!(config.game.ruleset.settings.royale.shrink_every_n_turns, 10);
+        assert_eq!(config.game.ruleset.settings.squad, SquadSettings::default());
+        assert_eq!(config.game.timeout, Some(300));
+        assert!(config.board.hazards[0] == Coords { x: 0, y: 0 });
+        assert!(config.you.head == Coords { x: 0, y: 1 });
+        assert_eq!(config.you.length, 3);
+        assert_eq!(config.you.customizations.head, "pixel");
+    }
+
+}
@@ -0,0 +1,381 @@
+//
+// Board evaluation. Scores game states from our point of view, as a weighted sum of named
+// features of the board.
+//
+
+use std::cell::OnceCell;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::api::SnakeConfig;
+use super::map::{Map, Region};
+use super::path::distance_field;
+use super::rules::Mode;
+use super::territory::Territory;
+use super::topology::Shape;
+use super::utils::Move;
+
+/// Something about a game state that makes it better or worse for us.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum Feature {
+    /// Cells in the biggest region we can get into next turn.
+    Area,
+    /// Cells we get to first, less those of the enemy that gets to the most first.
+    Territory,
+    /// Number of segments we're longer than the longest enemy. Always zero in solo games,
+    /// where there's nothing to gain from growing.
+    Length,
+    Health,
+    /// Moves to the nearest food we can get to, or the width plus the height of the board
+    /// if we can't get to any.
+    FoodDistance,
+    /// Moves from our head to the nearest edge of the board. Always zero on wrapped
+    /// boards, which have no edges.
+    WallDistance,
+    /// Moves from our head to the centre of the board. Always zero on wrapped boards,
+    /// where every cell is as central as any other.
+    CentreDistance,
+    /// One if we can get to our tail, zero if we can't.
+    TailReachable,
+}
+
+/// Every feature, in the order they're listed in breakdowns.
+pub const FEATURES: [Feature; 8] = [
+    Feature::Area,
+    Feature::Territory,
+    Feature::Length,
+    Feature::Health,
+    Feature::FoodDistance,
+    Feature::WallDistance,
+    Feature::CentreDistance,
+    Feature::TailReachable,
+];
+
+impl Feature {
+
+    /// Name of the feature, as its weight is called in config files.
+    pub fn name(self) -> &'static str {
+        match self {
+            Feature::Area => "area",
+            Feature::Territory => "territory",
+            Feature::Length => "length",
+            Feature::Health => "health",
+            Feature::FoodDistance => "food_distance",
+            Feature::WallDistance => "wall_distance",
+            Feature::CentreDistance => "centre_distance",
+            Feature::TailReachable => "tail_reachable",
+        }
+    }
+
+}
+
+/// Value of each unit of each feature. Features with a weight of zero aren't worked out
+/// at all when evaluating.
+///
+/// The defaults only score room to move, length and health.
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Weights {
+    pub area: f64,
+    pub territory: f64,
+    pub length: f64,
+    pub health: f64,
+    pub food_distance: f64,
+    pub wall_distance: f64,
+    pub centre_distance: f64,
+    pub tail_reachable: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            area: 1.0,
+            territory: 0.0,
+            length: 5.0,
+            health: 0.2,
+            food_distance: 0.0,
+            wall_distance: 0.0,
+            centre_distance: 0.0,
+            tail_reachable: 0.0,
+        }
+    }
+}
+
+impl Weights {
+
+    pub fn get(&self, feature: Feature) -> f64 {
+        match feature {
+            Feature::Area => self.area,
+            Feature::Territory => self.territory,
+            Feature::Length => self.length,
+            Feature::Health => self.health,
+            Feature::FoodDistance => self.food_distance,
+            Feature::WallDistance => self.wall_distance,
+            Feature::CentreDistance => self.centre_distance,
+            Feature::TailReachable => self.tail_reachable,
+        }
+    }
+
+    pub fn set(&mut self, feature: Feature, weight: f64) {
+        let field = match feature {
+            Feature::Area => &mut self.area,
+            Feature::Territory => &mut self.territory,
+            Feature::Length => &mut self.length,
+            Feature::Health => &mut self.health,
+            Feature::FoodDistance => &mut self.food_distance,
+            Feature::WallDistance => &mut self.wall_distance,
+            Feature::CentreDistance => &mut self.centre_distance,
+            Feature::TailReachable => &mut self.tail_reachable,
+        };
+        *field = weight;
+    }
+
+}
+
+/// A feature's value in some state, along with its weight.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Term {
+    pub feature: Feature,
+    pub value: f64,
+    pub weight: f64,
+}
+
+impl Term {
+
+    /// How much the feature adds to the state's score.
+    pub fn score(&self) -> f64 {
+        self.value * self.weight
+    }
+
+}
+
+/// How each feature adds up to a state's score.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Breakdown {
+    /// Every feature, in the same order as `FEATURES`.
+    pub terms: Vec<Term>,
+}
+
+impl Breakdown {
+
+    pub fn total(&self) -> f64 {
+        self.terms.iter().map(Term::score).sum()
+    }
+
+}
+
+impl fmt::Display for Breakdown {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for term in self.terms.iter() {
+            writeln!(f, "{:<16} {:>8.2} x {:>6.2} = {:>8.2}",
+                term.feature.name(), term.value, term.weight, term.score())?;
+        }
+
+        write!(f, "{:<16} {:>29.2}", "total", self.total())
+    }
+}
+
+/// State being evaluated. Holds on to anything several features need, so it's only worked
+/// out once.
+struct Position<'a> {
+    config: &'a SnakeConfig,
+    map: &'a Map,
+    regions: OnceCell<Vec<(Move, Region)>>,
+}
+
+impl<'a> Position<'a> {
+
+    fn regions(&self) -> &[(Move, Region)] {
+        self.regions.get_or_init(|| self.map.move_regions())
+    }
+
+}
+
+/// Scores game states the game carries on from, with us still on the board. Higher is
+/// better.
+#[derive(Clone, Debug, Default)]
+pub struct Evaluator {
+    weights: Weights,
+}
+
+impl Evaluator {
+
+    pub fn new(weights: Weights) -> Evaluator {
+        Evaluator { weights }
+    }
+
+    pub fn evaluate(&self, config: &SnakeConfig, map: &Map) -> f64 {
+        let position = Position { config, map, regions: OnceCell::new() };
+
+        FEATURES.iter()
+            .map(|feature| (self.weights.get(*feature), *feature))
+            .filter(|(weight, _)| *weight != 0.0)
+            .map(|(weight, feature)| weight * measure(feature, &position))
+            .sum()
+    }
+
+    /// Value of every feature in a state, and how much each adds to its score.
+    pub fn breakdown(&self, config: &SnakeConfig, map: &Map) -> Breakdown {
+        let position = Position { config, map, regions: OnceCell::new() };
+
+        Breakdown {
+            terms: FEATURES.iter()
+                .map(|feature| Term {
+                    feature: *feature,
+                    value: measure(*feature, &position),
+                    weight: self.weights.get(*feature),
+                })
+                .collect(),
+        }
+    }
+
+}
+
+fn measure(feature: Feature, position: &Position) -> f64 {
+    let Position { config, map, .. } = position;
+    let (x, y) = map.head();
+
+    match feature {
+        Feature::Area => {
+            position.regions().iter()
+                .map(|(_, region)| region.area)
+                .max()
+                .unwrap_or(0) as f64
+        },
+        Feature::Territory => {
+            let territory = Territory::new(config, map);
+
+            let ours = territory.claim_of(&config.you.id).map_or(0, |claim| claim.cells);
+            let theirs = territory.claims.iter()
+                .filter(|claim| claim.id != config.you.id)
+                .map(|claim| claim.cells)
+                .max()
+                .unwrap_or(0);
+
+            ours as f64 - theirs as f64
+        },
+        Feature::Length if map.mode() == Mode::Solo => 0.0,
+        Feature::Length => {
+            let longest_enemy = config.board.snakes.iter()
+                .filter(|snake| snake.id != config.you.id)
+                .map(|snake| snake.body.len())
+                .max()
+                .unwrap_or(0);
+
+            map.length() as f64 - longest_enemy as f64
+        },
+        Feature::Health => config.you.health as f64,
+        Feature::FoodDistance => {
+            let field = distance_field(map, map.head());
+
+            config.board.food.iter()
+                .filter_map(|food| field.distance_to((food.x, food.y)))
+                .min()
+                .unwrap_or(map.width + map.height) as f64
+        },
+        Feature::WallDistance => map.topology().edge_distance((x, y)).unwrap_or(0) as f64,
+        Feature::CentreDistance if map.topology().shape == Shape::Torus => 0.0,
+        Feature::CentreDistance => {
+            let centre_x = (map.width - 1) as f64 / 2.0;
+            let centre_y = (map.height - 1) as f64 / 2.0;
+
+            (x as f64 - centre_x).abs() + (y as f64 - centre_y).abs()
+        },
+        Feature::TailReachable => {
+            let reachable = position.regions().iter().any(|(_, region)| region.reaches_tail);
+            if reachable { 1.0 } else { 0.0 }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::snake::api::*;
+
+    fn snake(id: &str, body: Vec<Coords>) -> Snake {
+        Snake {
+            id: String::from(id),
+            body,
+            health: 50,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn prefers_room_to_move() {
+        // Board state:
+        //   - S - - -
+        //   Y S - - -
+        //   S S - - -
+        //
+        // Boxed into the corner by a long snake, we've got less room than if we were out
+        // in the open.
+        let you = snake("you", vec!(Coords { x: 0, y: 1 }, Coords { x: 0, y: 2 }, Coords { x: 1, y: 2 }));
+        let mut wall = snake("wall", vec!(Coords { x: 1, y: 1 }, Coords { x: 1, y: 0 }));
+        wall.body.resize(8, Coords { x: 1, y: 0 });
+
+        let boxed_in = SnakeConfig {
+            board: Board {
+                width: 5,
+                height: 3,
+                snakes: vec!(you.clone(), wall.clone()),
+                ..Default::default()
+            },
+            you: you.clone(),
+            ..Default::default()
+        };
+
+        let mut open = boxed_in.clone();
+        open.board.snakes[1].body = vec!(Coords { x: 4, y: 1 }, Coords { x: 4, y: 0 });
+        open.board.snakes[1].body.resize(8, Coords { x: 4, y: 0 });
+
+        let evaluator = Evaluator::default();
+        assert!(evaluator.evaluate(&open, &Map::new(&open)) > evaluator.evaluate(&boxed_in, &Map::new(&boxed_in)));
+    }
+
+    #[test]
+    fn breaks_score_down_by_feature() {
+        // Board state:
+        //   - - - - -
+        //   - Y - - -
+        //   - S - - F
+        //   - S - - -
+        //   - - - - -
+        let you = snake("you", vec!(Coords { x: 1, y: 1 }, Coords { x: 1, y: 2 }, Coords { x: 1, y: 3 }));
+        let config = SnakeConfig {
+            board: Board {
+                width: 5,
+                height: 5,
+                food: vec!(Coords { x: 4, y: 2 }),
+                snakes: vec!(you.clone()),
+                ..Default::default()
+            },
+            you,
+            ..Default::default()
+        };
+        let map = Map::new(&config);
+
+        let weights = Weights { food_distance: -1.0, centre_distance: -2.0, ..Default::default() };
+        let evaluator = Evaluator::new(weights);
+        let breakdown = evaluator.breakdown(&config, &map);
+
+        let value = |feature| breakdown.terms.iter().find(|term| term.feature == feature).unwrap().value;
+        assert_eq!(value(Feature::Area), 25.0);
+        assert_eq!(value(Feature::Territory), 25.0);
+        assert_eq!(value(Feature::Length), 3.0);
+        assert_eq!(value(Feature::Health), 50.0);
+        assert_eq!(value(Feature::FoodDistance), 4.0);
+        assert_eq!(value(Feature::WallDistance), 1.0);
+        assert_eq!(value(Feature::CentreDistance), 2.0);
+        assert_eq!(value(Feature::TailReachable), 1.0);
+
+        // Features without a weight are left out of the score, but still broken down
+        assert_eq!(breakdown.terms.len(), FEATURES.len());
+        assert_eq!(breakdown.total(), evaluator.evaluate(&config, &map));
+        assert_eq!(breakdown.total(), 25.0 + 5.0 * 3.0 + 0.2 * 50.0 - 4.0 - 4.0);
+    }
+
+}
@@ -1,50 +1,83 @@
+use queues::*;
+use std::cmp::Reverse;
+use std::collections::HashSet;
+
 use crate::snake::utils::TwoDimensionalMap;
 use crate::snake::api::SnakeConfig;
+use super::rules::{self, Mode};
+use super::topology::Topology;
 use super::utils::Move;
 
-#[derive(PartialEq, Clone, Copy, Debug)]
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum BoardSpace {
+    #[default]
     EMPTY,
     SNAKE,
     FOOD,
 }
 
-impl Default for BoardSpace {
-    fn default() -> Self { BoardSpace::EMPTY }
+/// Whether an enemy snake's head could move into a cell next turn, and how a head-to-head
+/// collision there would go for us. Ordered from best to worst.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Default)]
+pub enum Contest {
+    /// Only shorter snakes can reach the cell, so we'd win a collision.
+    Favourable,
+    /// No enemy heads can reach the cell.
+    #[default]
+    Uncontested,
+    /// A snake at least as long as us can reach the cell, so we'd lose a collision.
+    Dangerous,
 }
 
-// Macros for move types
-macro_rules! left {
-    ($coords:expr) => {
-        ($coords.0 - 1, $coords.1)
-    }
-}
-macro_rules! right {
-    ($coords:expr) => {
-        ($coords.0 + 1, $coords.1)
-    }
-}
-macro_rules! up {
-    ($coords:expr) => {
-        ($coords.0, $coords.1 - 1)
-    }
-}
-macro_rules! down {
-    ($coords:expr) => {
-        ($coords.0, $coords.1 + 1)
-    }
+/// Part of the board a snake can reach.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Region {
+    /// Number of cells in the region.
+    pub area: u32,
+
+    /// Whether our tail is in the region. A region containing our tail is never a trap,
+    /// since we can always follow it.
+    pub reaches_tail: bool,
 }
 
 pub struct Map {
     pub width: u32,
     pub height: u32,
 
+    /// How the board's cells join up, which decides where moves lead.
+    topology: Topology,
+
     // FIXME: using a matrix representation will use a lot of memory on large
     // boards. Use some sort of std::map equivalent?
     vals: TwoDimensionalMap<BoardSpace>,
 
+    /// Cells enemy heads can reach next turn.
+    contested: TwoDimensionalMap<Contest>,
+
+    /// Number of turns until each cell is free of snakes, assuming no snake eats in the
+    /// meantime. A tail is free after one turn, since it moves away as the snake moves.
+    free_at: TwoDimensionalMap<u32>,
+
+    /// Cells that hurt snakes with their heads in them.
+    hazards: TwoDimensionalMap<bool>,
+
+    /// Health lost each turn with our head in a hazard, on top of the usual one.
+    hazard_damage: u32,
+
+    mode: Mode,
+
     /// Location of your snake's head.
-    you_head: (u32, u32)
+    you_head: (u32, u32),
+
+    /// Location of your snake's tail.
+    you_tail: (u32, u32),
+
+    /// Length of your snake.
+    you_len: usize,
+
+    /// Health of your snake.
+    you_health: u8,
 }
 
 impl Map {
@@ -60,47 +93,153 @@ impl Map {
             vals[(coords.x as usize, coords.y as usize)] = BoardSpace::FOOD;
         }
 
+        let mut free_at = TwoDimensionalMap::new(board.width as usize, board.height as usize);
+        let mode = Mode::from_name(&config.game.ruleset.name);
+        let topology = Topology::of(config);
+        let squad = &config.game.ruleset.settings.squad;
+
         for snake in board.snakes.iter() {
-            for coords in snake.body.iter() {
-                vals[(coords.x as usize, coords.y as usize)] = BoardSpace::SNAKE;
+            // Squadmates can be set up so we can move right through them
+            let passable = mode == Mode::Squad && squad.allow_body_collisions
+                && snake.id != config.you.id && rules::same_squad(snake, &config.you);
+            if passable {
+                continue;
+            }
+
+            let len = snake.body.len() as u32;
+
+            for (idx, coords) in snake.body.iter().enumerate() {
+                let cell = (coords.x as usize, coords.y as usize);
+                vals[cell] = BoardSpace::SNAKE;
+
+                // Segments stacked on the same cell (after eating, or at the start of the
+                // game) keep it occupied until the last of them has moved on. Constrictor
+                // snakes grow every turn, so their bodies never move on at all.
+                free_at[cell] = match mode {
+                    Mode::Constrictor => u32::MAX,
+                    _ => std::cmp::max(free_at[cell], len - idx as u32),
+                };
+            }
+        }
+
+        let mut hazards = TwoDimensionalMap::new(board.width as usize, board.height as usize);
+        for coords in board.hazards.iter() {
+            hazards[(coords.x as usize, coords.y as usize)] = true;
+        }
+
+        // Our body may have been deduplicated, so go by the board's copy of us (which
+        // still has any stacked segments) to compare lengths
+        let you_len = board.snakes.iter()
+            .find(|snake| snake.id == config.you.id)
+            .map(|snake| snake.body.len())
+            .unwrap_or_else(|| config.you.body.len());
+
+        let mut contested = TwoDimensionalMap::new(board.width as usize, board.height as usize);
+
+        for snake in board.snakes.iter().filter(|snake| snake.id != config.you.id) {
+            let contest = if snake.body.len() >= you_len {
+                Contest::Dangerous
+            }
+            else {
+                Contest::Favourable
+            };
+
+            let head = (snake.body[0].x, snake.body[0].y);
+            for coords in topology.neighbours(head) {
+                let cell = &mut contested[(coords.0 as usize, coords.1 as usize)];
+
+                // Favourable cells only stay favourable if no longer snake can get there
+                *cell = match (*cell, contest) {
+                    (Contest::Uncontested, contest) => contest,
+                    (current, contest) => current.max(contest),
+                };
             }
         }
 
+        let you_tail = config.you.body[config.you.body.len() - 1];
+
         Map {
             width: board.width,
             height: board.height,
-            vals: vals,
-            you_head: (config.you.body[0].x, config.you.body[0].y)
+            topology,
+            vals,
+            contested,
+            free_at,
+            hazards,
+            hazard_damage: config.game.ruleset.settings.hazard_damage_per_turn,
+            mode,
+            you_head: (config.you.body[0].x, config.you.body[0].y),
+            you_tail: (you_tail.x, you_tail.y),
+            you_len,
+            you_health: config.you.health,
         }
     }
 
-    pub fn at(&self, x: u32, y: u32) -> BoardSpace {
-        self.vals[(x as usize, y as usize)]
+    /// Cells next to the given cell, in the order right, left, up, down.
+    pub fn neighbours(&self, coords: (u32, u32)) -> Vec<(u32, u32)> {
+        self.topology.neighbours(coords)
     }
 
-    /// Whether or not moving a given direction is safe (not a snake and not out
-    /// or bounds).
-    pub fn is_safe_move(&self, move_req: Move) -> bool {
-        let head = &self.you_head;
+    pub fn topology(&self) -> &Topology {
+        &self.topology
+    }
 
-        match move_req {
+    /// Location of your snake's head.
+    pub fn head(&self) -> (u32, u32) {
+        self.you_head
+    }
 
-            Move::Up => {
-                head.1 != 0 && self.is_safe_node(up!(head))
-            },
+    /// Location of your snake's tail.
+    pub fn tail(&self) -> (u32, u32) {
+        self.you_tail
+    }
 
-            Move::Down => {
-                head.1 + 1 < self.height && self.is_safe_node(down!(head))
-            },
+    /// Length of your snake, counting any segments stacked on the same cell.
+    pub fn length(&self) -> usize {
+        self.you_len
+    }
 
-            Move::Left => {
-                head.0 != 0 && self.is_safe_node(left!(head))
-            },
+    /// Number of turns until a cell is free of snakes. Zero for cells that are free now.
+    pub fn free_at(&self, x: u32, y: u32) -> u32 {
+        self.free_at[(x as usize, y as usize)]
+    }
 
-            Move::Right => {
-                head.0 + 1 < self.width && self.is_safe_node(right!(head))
-            }
+    /// Whether a cell can be entered `turns` turns from now (in bounds, and no snake in
+    /// the way by then).
+    pub fn is_passable(&self, coords: (u32, u32), turns: u32) -> bool {
+        coords.0 < self.width && coords.1 < self.height && self.free_at(coords.0, coords.1) <= turns
+    }
 
+    /// How a head-to-head collision in a cell would go if we moved there next turn.
+    pub fn contest_at(&self, x: u32, y: u32) -> Contest {
+        self.contested[(x as usize, y as usize)]
+    }
+
+    pub fn at(&self, x: u32, y: u32) -> BoardSpace {
+        self.vals[(x as usize, y as usize)]
+    }
+
+    pub fn is_hazard(&self, x: u32, y: u32) -> bool {
+        self.hazards[(x as usize, y as usize)]
+    }
+
+    /// Mode of the game being played.
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Whether moving our head into a cell next turn would use up the last of our health.
+    fn starves_in(&self, coords: (u32, u32)) -> bool {
+        let damage = if self.is_hazard(coords.0, coords.1) { self.hazard_damage + 1 } else { 1 };
+        self.at(coords.0, coords.1) != BoardSpace::FOOD && self.you_health as u32 <= damage
+    }
+
+    /// Whether or not moving a given direction is safe (not out of bounds, not a snake
+    /// that will still be there after this turn, and not somewhere we'd run out of health).
+    pub fn is_safe_move(&self, move_req: Move) -> bool {
+        match self.topology.step(self.you_head, move_req) {
+            Some(target) => self.is_passable(target, 1) && !self.starves_in(target),
+            None => false,
         }
     }
 
@@ -120,26 +259,93 @@ impl Map {
         true
     }
 
-    /// Find any move that won't (immediately) kill you
+    /// Cell your head ends up in after a move. The move must be in bounds.
+    fn target(&self, move_val: Move) -> (u32, u32) {
+        self.topology.step(self.you_head, move_val).expect("move leaves the board")
+    }
+
+    /// How a head-to-head collision would go after a move. The move must be in bounds.
+    fn contest_after(&self, move_val: Move) -> Contest {
+        let coords = self.target(move_val);
+        self.contest_at(coords.0, coords.1)
+    }
+
+    /// Moves that won't (immediately) kill you, in the order right, left, up, down.
+    fn safe_moves(&self) -> Vec<Move> {
+        [Move::Right, Move::Left, Move::Up, Move::Down].iter()
+            .copied()
+            .filter(|move_val| self.is_safe_move(*move_val))
+            .collect()
+    }
+
+    /// Find any move that won't (immediately) kill you. Moves that could win a
+    /// head-to-head collision are preferred, and moves that could lose one are only made
+    /// if there's nothing else.
     pub fn find_safe_move(&self) -> Move {
         // No point in returning an option here. If there's no safe moves then
-        // admit defeat and go left.
-        let mut move_val = Move::Left;
+        // admit defeat and go left. Ties go to the first move in the list.
+        self.safe_moves().into_iter()
+            .min_by_key(|move_val| self.contest_after(*move_val))
+            .unwrap_or(Move::Left)
+    }
 
-        if self.is_safe_move(Move::Right) {
-            move_val = Move::Right;
-        }
-        else if self.is_safe_move(Move::Left) {
-            move_val = Move::Left;
+    /// Flood fills the board from a cell we could step into next turn, to find how much
+    /// room there is on that side. Cells count as part of the region if they'll be free of
+    /// snakes by the time we could get to them.
+    pub fn reachable_region(&self, start: (u32, u32)) -> Region {
+        let mut q: Queue<((u32, u32), u32)> = Queue::new();
+        let mut traversed: HashSet<(u32, u32)> = HashSet::new();
+
+        q.add((start, 1)).unwrap();
+
+        while q.size() > 0 {
+            let (coords, dist) = q.remove().unwrap();
+
+            if traversed.contains(&coords) || !self.is_passable(coords, dist) {
+                continue;
+            }
+
+            traversed.insert(coords);
+
+            for neighbour in self.neighbours(coords) {
+                q.add((neighbour, dist + 1)).unwrap();
+            }
         }
-        else if self.is_safe_move(Move::Up) {
-            move_val = Move::Up;
+
+        Region {
+            area: traversed.len() as u32,
+            reaches_tail: traversed.contains(&self.you_tail),
         }
-        else if self.is_safe_move(Move::Down) {
-            move_val = Move::Down;
+    }
+
+    /// Region of the board reachable after each move that won't (immediately) kill you.
+    pub fn move_regions(&self) -> Vec<(Move, Region)> {
+        self.safe_moves().into_iter()
+            .map(|move_val| (move_val, self.reachable_region(self.target(move_val))))
+            .collect()
+    }
+
+    /// Find a move that won't kill you now, and won't trap you in a region too small to
+    /// fit in either. If every move leads to a trap, goes for the biggest one to buy as
+    /// much time as possible. Otherwise picks moves the same way as `find_safe_move`.
+    pub fn find_spacious_move(&self) -> Move {
+        let regions = self.move_regions();
+
+        let roomy: Vec<Move> = regions.iter()
+            .filter(|(_, region)| region.area as usize >= self.you_len || region.reaches_tail)
+            .map(|(move_val, _)| *move_val)
+            .collect();
+
+        let best = if !roomy.is_empty() {
+            roomy.into_iter().min_by_key(|move_val| self.contest_after(*move_val))
         }
+        else {
+            regions.iter()
+                .min_by_key(|(move_val, region)| (Reverse(region.area), self.contest_after(*move_val)))
+                .map(|(move_val, _)| *move_val)
+        };
 
-        move_val
+        best.unwrap_or(Move::Left)
     }
 
 }
@@ -171,6 +377,7 @@ mod tests {
                     Coords { x: 22, y: 18 },
                     Coords { x: 5,  y: 2 }
                 ),
+                ..Default::default()
             },
             ..Default::default()
         };
@@ -191,4 +398,369 @@ mod tests {
         }
     }
 
+    /// Board with us heading down the left side, and an enemy of the given length heading
+    /// down two cells to the right.
+    ///
+    ///   S - E -
+    ///   Y - E -
+    ///   - - - -
+    fn head_to_head_config(enemy_len: usize) -> SnakeConfig {
+        let you = Snake {
+            id: String::from("you"),
+            body: vec!(Coords { x: 0, y: 1 }, Coords { x: 0, y: 0 }, Coords { x: 0, y: 0 }),
+            ..Default::default()
+        };
+
+        let mut enemy_body = vec!(Coords { x: 2, y: 1 }, Coords { x: 2, y: 0 });
+        enemy_body.resize(enemy_len, Coords { x: 2, y: 0 });
+
+        SnakeConfig {
+            board: Board {
+                width: 4,
+                height: 3,
+                snakes: vec!(
+                    you.clone(),
+                    Snake {
+                        id: String::from("enemy"),
+                        body: enemy_body,
+                        ..Default::default()
+                    }
+                ),
+                ..Default::default()
+            },
+            you,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn tracks_when_cells_become_free() {
+        let config = SnakeConfig {
+            board: Board {
+                width: 5,
+                height: 5,
+                snakes: vec!(
+                    Snake {
+                        body: vec!(
+                            Coords { x: 1, y: 1 },
+                            Coords { x: 1, y: 2 },
+                            Coords { x: 2, y: 2 },
+                        ),
+                        ..Default::default()
+                    },
+                    Snake {
+                        id: String::from("fed"),
+                        body: vec!(
+                            Coords { x: 4, y: 0 },
+                            Coords { x: 4, y: 1 },
+                            Coords { x: 4, y: 1 },
+                        ),
+                        ..Default::default()
+                    }
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let map = Map::new(&config);
+
+        assert_eq!(map.free_at(1, 1), 3);
+        assert_eq!(map.free_at(1, 2), 2);
+        assert_eq!(map.free_at(2, 2), 1);
+        assert_eq!(map.free_at(0, 0), 0);
+
+        // A snake that just ate leaves its tail in place for an extra turn
+        assert_eq!(map.free_at(4, 1), 2);
+
+        assert!(map.is_passable((2, 2), 1));
+        assert!(!map.is_passable((1, 2), 1));
+        assert!(map.is_passable((1, 2), 2));
+        assert!(!map.is_passable((5, 2), 10));
+    }
+
+    #[test]
+    fn safe_move_can_follow_a_tail() {
+        // Board state:
+        //   E E Y S
+        //
+        // Boxed in on a single-row board. The enemy's tail will have moved on by the time
+        // we get there, so we can follow it.
+        let you = Snake {
+            id: String::from("you"),
+            body: vec!(Coords { x: 2, y: 0 }, Coords { x: 3, y: 0 }),
+            ..Default::default()
+        };
+
+        let config = SnakeConfig {
+            board: Board {
+                width: 4,
+                height: 1,
+                snakes: vec!(
+                    you.clone(),
+                    Snake {
+                        id: String::from("enemy"),
+                        body: vec!(Coords { x: 0, y: 0 }, Coords { x: 1, y: 0 }),
+                        ..Default::default()
+                    }
+                ),
+                ..Default::default()
+            },
+            you,
+            ..Default::default()
+        };
+
+        let map = Map::new(&config);
+        assert!(map.is_safe_move(Move::Left));
+        assert!(!map.is_safe_move(Move::Up));
+    }
+
+    /// Board with us next to a six cell pocket in the top left corner. The pocket's walls
+    /// belong to long snakes, so they won't move out of the way any time soon.
+    ///
+    ///     - - - W - -
+    ///     - - - W - -
+    ///     W W Y S S S
+    ///     - - - - - S
+    ///     - - - - S S
+    fn pocket_config(you_len: usize) -> SnakeConfig {
+        let body = [
+            Coords { x: 2, y: 2 },
+            Coords { x: 3, y: 2 },
+            Coords { x: 4, y: 2 },
+            Coords { x: 5, y: 2 },
+            Coords { x: 5, y: 3 },
+            Coords { x: 5, y: 4 },
+            Coords { x: 4, y: 4 },
+        ];
+
+        let you = Snake {
+            id: String::from("you"),
+            body: body[..you_len].to_vec(),
+            ..Default::default()
+        };
+
+        let wall = |id: &str, body: Vec<Coords>| {
+            let mut body = body;
+            body.resize(6, body[body.len() - 1]);
+            Snake {
+                id: String::from(id),
+                body,
+                ..Default::default()
+            }
+        };
+
+        SnakeConfig {
+            board: Board {
+                width: 6,
+                height: 5,
+                snakes: vec!(
+                    you.clone(),
+                    wall("left", vec!(Coords { x: 0, y: 2 }, Coords { x: 1, y: 2 })),
+                    wall("top", vec!(Coords { x: 3, y: 0 }, Coords { x: 3, y: 1 })),
+                ),
+                ..Default::default()
+            },
+            you,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn flood_fill_measures_regions() {
+        let map = Map::new(&pocket_config(7));
+
+        let up = map.reachable_region((2, 1));
+        assert_eq!(up.area, 6);
+        assert!(!up.reaches_tail);
+
+        // Our tail moves out of the way in time to be part of the region
+        let down = map.reachable_region((2, 3));
+        assert!(down.area > up.area);
+        assert!(down.reaches_tail);
+    }
+
+    #[test]
+    fn spacious_move_avoids_small_regions() {
+        // Up is the first safe move, but leads into the pocket, which is too small to fit
+        // a long snake
+        let map = Map::new(&pocket_config(7));
+        assert_eq!(map.find_safe_move(), Move::Up);
+        assert_eq!(map.find_spacious_move(), Move::Down);
+
+        // A short snake fits in the pocket just fine
+        let map = Map::new(&pocket_config(3));
+        assert_eq!(map.find_spacious_move(), Move::Up);
+    }
+
+    #[test]
+    fn marks_cells_next_to_enemy_heads() {
+        let map = Map::new(&head_to_head_config(3));
+
+        assert_eq!(map.contest_at(1, 1), Contest::Dangerous);
+        assert_eq!(map.contest_at(3, 1), Contest::Dangerous);
+        assert_eq!(map.contest_at(2, 2), Contest::Dangerous);
+        assert_eq!(map.contest_at(0, 2), Contest::Uncontested);
+
+        // Our own head doesn't contest anything
+        let map = Map::new(&head_to_head_config(2));
+        assert_eq!(map.contest_at(1, 1), Contest::Favourable);
+        assert_eq!(map.contest_at(0, 2), Contest::Uncontested);
+    }
+
+    #[test]
+    fn safe_move_avoids_losing_head_to_head() {
+        // Moving right could run into an equally long snake, so go down instead
+        let map = Map::new(&head_to_head_config(3));
+        assert_eq!(map.find_safe_move(), Move::Down);
+    }
+
+    #[test]
+    fn safe_move_prefers_winning_head_to_head() {
+        let map = Map::new(&head_to_head_config(2));
+        assert_eq!(map.find_safe_move(), Move::Right);
+    }
+
+    /// A 5x5 board with us in the middle, heading up, and another snake in the given mode.
+    fn mode_config(mode: &str, other: Snake) -> SnakeConfig {
+        let you = Snake {
+            id: String::from("you"),
+            body: vec!(Coords { x: 2, y: 2 }, Coords { x: 2, y: 3 }, Coords { x: 2, y: 4 }),
+            squad: String::from("red"),
+            ..Default::default()
+        };
+
+        SnakeConfig {
+            game: Game {
+                ruleset: Ruleset { name: String::from(mode), ..Default::default() },
+                ..Default::default()
+            },
+            board: Board {
+                width: 5,
+                height: 5,
+                snakes: vec!(you.clone(), other),
+                ..Default::default()
+            },
+            you,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn constrictor_bodies_stay_put() {
+        let other = Snake {
+            id: String::from("other"),
+            body: vec!(Coords { x: 0, y: 0 }, Coords { x: 0, y: 1 }),
+            ..Default::default()
+        };
+
+        // Tails usually move out of the way, but constrictor snakes grow every turn
+        let map = Map::new(&mode_config("standard", other.clone()));
+        assert!(map.is_passable((2, 4), 1));
+        assert!(map.is_passable((0, 1), 1));
+
+        let map = Map::new(&mode_config("constrictor", other));
+        assert_eq!(map.mode(), Mode::Constrictor);
+        assert!(!map.is_passable((2, 4), 100));
+        assert!(!map.is_passable((0, 1), 100));
+        assert!(map.is_passable((1, 1), 1));
+    }
+
+    #[test]
+    fn wrapped_edges_lead_around() {
+        // Board state:
+        //   S - - - -
+        //   E - - - -
+        //   Y S S - -
+        //   S S - - -
+        //   S - - - -
+        //
+        // The enemy wraps around from the top of the board to the bottom. We're boxed in
+        // against the edge, unless the edge leads around to the other side.
+        let other = Snake {
+            id: String::from("other"),
+            body: vec!(
+                Coords { x: 0, y: 1 },
+                Coords { x: 0, y: 0 },
+                Coords { x: 0, y: 4 },
+                Coords { x: 0, y: 3 },
+                Coords { x: 1, y: 3 },
+            ),
+            ..Default::default()
+        };
+        let mut config = mode_config("standard", other);
+        config.you.body = vec!(Coords { x: 0, y: 2 }, Coords { x: 1, y: 2 }, Coords { x: 2, y: 2 });
+        config.board.snakes[0].body = config.you.body.clone();
+
+        let map = Map::new(&config);
+        assert_eq!(map.neighbours((0, 2)), vec!((1, 2), (0, 1), (0, 3)));
+        assert!(map.move_regions().is_empty());
+
+        config.game.ruleset.name = String::from("wrapped");
+        let map = Map::new(&config);
+        assert_eq!(map.neighbours((0, 2)), vec!((1, 2), (4, 2), (0, 1), (0, 3)));
+        assert_eq!(map.contest_at(4, 1), Contest::Dangerous);
+        assert_eq!(map.find_spacious_move(), Move::Left);
+
+        let regions = map.move_regions();
+        assert_eq!(regions.len(), 1);
+        assert!(regions[0].1.reaches_tail);
+    }
+
+    #[test]
+    fn avoids_hazards_that_would_starve_us() {
+        // Board state (H is a hazard):
+        //   - - - - -
+        //   - - - - -
+        //   - H Y - -
+        //   - - S - -
+        //   - - S - -
+        let other = Snake {
+            id: String::from("other"),
+            body: vec!(Coords { x: 4, y: 0 }),
+            ..Default::default()
+        };
+        let mut config = mode_config("royale", other);
+        config.board.hazards = vec!(Coords { x: 1, y: 2 });
+
+        let map = Map::new(&config);
+        assert!(map.is_hazard(1, 2));
+        assert!(map.is_safe_move(Move::Left));
+
+        // The hazard takes the last of our health
+        config.you.health = 15;
+        let map = Map::new(&config);
+        assert!(!map.is_safe_move(Move::Left));
+        assert!(map.is_safe_move(Move::Right));
+
+        // ...unless there's food to eat there
+        config.board.food = vec!(Coords { x: 1, y: 2 });
+        let map = Map::new(&config);
+        assert!(map.is_safe_move(Move::Left));
+    }
+
+    #[test]
+    fn squadmates_are_not_obstacles() {
+        // Board state:
+        //   - - - - -
+        //   - - - - -
+        //   - - Y M M
+        //   - - S - M
+        //   - - S - -
+        let mut mate = Snake {
+            id: String::from("mate"),
+            body: vec!(Coords { x: 4, y: 3 }, Coords { x: 4, y: 2 }, Coords { x: 3, y: 2 }),
+            squad: String::from("red"),
+            ..Default::default()
+        };
+        mate.body.resize(6, Coords { x: 3, y: 2 });
+
+        let map = Map::new(&mode_config("squad", mate.clone()));
+        assert!(map.is_safe_move(Move::Right));
+
+        // Outside of squad games, squads don't mean anything
+        let map = Map::new(&mode_config("standard", mate));
+        assert!(!map.is_safe_move(Move::Right));
+    }
+
 }
@@ -3,57 +3,77 @@
 // 
 
 pub mod api;
+pub mod evaluator;
+pub mod rules;
+pub mod strategy;
+pub mod territory;
+pub mod utils;
 mod map;
-mod utils;
 mod path;
+mod search;
+mod topology;
+
+use std::time::Instant;
+
+use serde::Deserialize;
 
 use api::*;
 use map::Map;
-use utils::Move;
-use path::shortest_path_to;
+use strategy::Strategy;
+
+/// How a snake looks on the game board.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Appearance {
+    pub color: String,
+    pub head: String,
+    pub tail: String,
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Appearance {
+            color: String::from("#FF0000"),
+            head: String::from("beluga"),
+            tail: String::from("hook"),
+        }
+    }
+}
+
+pub fn handle_info(appearance: &Appearance) -> InfoResponse<'_> {
+    InfoResponse {
+        apiversion: "1",
+        author: "CDFriend",
+        color: &appearance.color,
+        head: &appearance.head,
+        tail: &appearance.tail,
+        version: env!("CARGO_PKG_VERSION"),
+    }
+}
+
+pub fn handle_start<'a>(strategy: &dyn Strategy, appearance: &'a Appearance, config: SnakeConfig)
+    -> StartResponse<'a> {
+
+    let map = Map::new(&config);
+    strategy.start(&config, &map);
 
-pub fn handle_start(_config: SnakeConfig) -> StartResponse {
     StartResponse {
-        color: "#FF0000",
-        headType: "beluga",
-        tailType: "hook",
+        color: &appearance.color,
+        headType: &appearance.head,
+        tailType: &appearance.tail,
     }
 }
 
-pub fn handle_move(mut config: SnakeConfig) -> MoveResponse {
+/// Picks a move. `received` is when the request came in, which the engine's timeout is
+/// counted from.
+pub fn handle_move(strategy: &dyn Strategy, mut config: SnakeConfig, received: Instant) -> MoveResponse {
 
     // Remove duplicates from body. This might happen at the beginning of the game,
     // where we're sent 3 of the same sets of coordinates.
     config.you.body.dedup();
 
-    // Chase your tail!
-    let head = &config.you.body[0];
-    let tail = &config.you.body[config.you.body.len() - 1];
-
     let map = Map::new(&config);
-    
-    let body = &config.you.body;
-    let mut move_val = Move::Left;
-    if body.len() < 3 {
-        // Special case where head and tail are the same node (should only be
-        // first move). Just try and find a direction that won't kill you.
-        move_val = map.find_safe_move();
-    }
-    else {
-        // Try to find your tail
-        move_val = match shortest_path_to(&map, (head.x, head.y), (tail.x, tail.y)) {
-            Some(path) => {
-                // We've already checked that the head and tail are not the same node,
-                // so we should have more than one node in our path
-                assert!(path[0].next_move.is_some());
-                path[0].next_move.unwrap_or(Move::Left)
-            },
-            None => {
-                // No way to find your tail, so just go somewhere safe
-                map.find_safe_move()
-            }
-        }
-    }
+    let move_val = strategy.next_move(&config, &map, received);
 
     MoveResponse {
         r#move: move_val.to_string(),
@@ -61,6 +81,7 @@ pub fn handle_move(mut config: SnakeConfig) -> MoveResponse {
     }
 }
 
-pub fn handle_end(_config: SnakeConfig) {
-
+pub fn handle_end(strategy: &dyn Strategy, config: SnakeConfig) {
+    let map = Map::new(&config);
+    strategy.end(&config, &map);
 }
@@ -3,11 +3,13 @@
 //
 
 use queues::*;
+use std::cmp::Ordering;
 use std::rc::Rc;
-use std::collections::HashSet;
+use std::collections::{BinaryHeap, HashSet};
 
-use super::map::Map;
-use super::utils::Move;
+use super::map::{Map, Contest};
+use super::topology::Topology;
+use super::utils::{Move, TwoDimensionalMap};
 
 struct BfsNode {
     /// Distance from root node
@@ -34,13 +36,62 @@ pub struct PathNode {
 
 /// Gets a path from the source node to the target node.
 pub fn shortest_path_to(map: &Map, start: (u32, u32), target: (u32, u32)) -> Option<Vec<PathNode>> {
+    shortest_path_after(map, start, target, 0)
+}
+
+
+/// Gets a path from the source node to the target node, setting off `turns` turns from
+/// now. Cells along the way only need to be free of snakes by the time we'd get to them.
+pub fn shortest_path_after(map: &Map, start: (u32, u32), target: (u32, u32), turns: u32)
+    -> Option<Vec<PathNode>> {
 
     // Run BFS - is there a path to the target?
-    let mut cur_bfs_node = match bfs_to(map, start, target) {
-        Some(rc_target) => rc_target,
-        None => return None
-    };
+    let target_node = bfs_to(map, start, target, turns)?;
+
+    Some(unwind_path(map.topology(), target_node))
+}
+
+
+/// Gets the cheapest path from the source node to the target node, where `cost` gives the
+/// cost of stepping into a cell. Costs below one are treated as one, so the distance to
+/// the target never overestimates the cost of getting there.
+///
+/// Useful for routes that should stay safe rather than be as short as possible.
+pub fn cheapest_path_to<F>(map: &Map, start: (u32, u32), target: (u32, u32), cost: F)
+    -> Option<Vec<PathNode>> where F: Fn((u32, u32)) -> u32 {
+
+    let target_node = a_star_to(map, start, target, cost)?;
+
+    Some(unwind_path(map.topology(), target_node))
+}
+
+
+/// Cost function for `cheapest_path_to` that prefers keeping our options open. Cells on
+/// the edge of the board cost a little extra, and hazards and cells where we could lose a
+/// head-to-head collision cost a lot extra.
+pub fn cautious_cost(map: &Map) -> impl Fn((u32, u32)) -> u32 + '_ {
+    move |(x, y)| {
+        let mut cost = 1;
+
+        if map.topology().edge_distance((x, y)) == Some(0) {
+            cost += 1;
+        }
+
+        if map.contest_at(x, y) == Contest::Dangerous {
+            cost += 8;
+        }
+
+        if map.is_hazard(x, y) {
+            cost += 8;
+        }
+
+        cost
+    }
+}
+
 
+/// Turns the last node of a search into a path from the search's source node.
+fn unwind_path(topology: &Topology, mut cur_bfs_node: Rc<BfsNode>) -> Vec<PathNode> {
     // Follow path backwards until we reach the source node.
     // Total required space for the path should be equal to the distance of the path.
     let mut path = Vec::<PathNode>::with_capacity((cur_bfs_node.dist + 1) as usize);
@@ -48,30 +99,17 @@ pub fn shortest_path_to(map: &Map, start: (u32, u32), target: (u32, u32)) -> Opt
     let mut idx = 0;
     loop {
 
-        // Determine next move
-        let mut next_move : Option<Move> = None;
-        if idx > 0 {
-
-            // Determine the required move from the current node to the next one in the path
-            let next_node = &path[idx - 1];
-
-            if cur_bfs_node.x > next_node.coords.0 {
-                next_move = Some(Move::Left);
-            }
-            else if cur_bfs_node.x < next_node.coords.0 {
-                next_move = Some(Move::Right);
-            }
-            else if cur_bfs_node.y > next_node.coords.1 {
-                next_move = Some(Move::Up);
-            }
-            else if cur_bfs_node.y < next_node.coords.1 {
-                next_move = Some(Move::Down);
-            }
+        // Determine the required move from the current node to the next one in the path
+        let next_move = if idx > 0 {
+            move_between(topology, (cur_bfs_node.x, cur_bfs_node.y), path[idx - 1].coords)
         }
+        else {
+            None
+        };
 
         path.push(PathNode{
             coords: (cur_bfs_node.x, cur_bfs_node.y),
-            next_move: next_move
+            next_move
         });
 
         match &cur_bfs_node.prev {
@@ -79,12 +117,127 @@ pub fn shortest_path_to(map: &Map, start: (u32, u32), target: (u32, u32)) -> Opt
             None => break
         };
 
-        idx = idx + 1;
+        idx += 1;
     }
 
     path.reverse();
 
-    Some(path)
+    path
+}
+
+
+/// Move that takes you from one cell to a neighbouring one.
+fn move_between(topology: &Topology, from: (u32, u32), to: (u32, u32)) -> Option<Move> {
+    [Move::Right, Move::Left, Move::Up, Move::Down].iter()
+        .copied()
+        .find(|move_val| topology.step(from, *move_val) == Some(to))
+}
+
+
+/// Shortest distances from a source cell to every cell on the board, found with a single
+/// breadth-first search. Cells are entered under the same rules as `shortest_path_to`, so
+/// the distance and path to any cell match what it would find.
+pub struct DistanceField {
+    source: (u32, u32),
+
+    topology: Topology,
+
+    /// Number of moves needed to reach each cell, or None if it can't be reached.
+    dist: TwoDimensionalMap<Option<u32>>,
+
+    /// Cell each cell was first reached from.
+    reached_from: TwoDimensionalMap<Option<(u32, u32)>>,
+
+    /// Cell each cell was reached from when the search carried on through it. Cells that
+    /// are blocked when first reached may only be passed through along a longer path, once
+    /// they've been vacated.
+    passed_from: TwoDimensionalMap<Option<(u32, u32)>>,
+}
+
+impl DistanceField {
+
+    /// Number of moves needed to reach a cell from the source, or None if it can't be
+    /// reached.
+    pub fn distance_to(&self, coords: (u32, u32)) -> Option<u32> {
+        self.dist[(coords.0 as usize, coords.1 as usize)]
+    }
+
+    /// Shortest path from the source to a cell, or None if it can't be reached.
+    pub fn path_to(&self, target: (u32, u32)) -> Option<Vec<PathNode>> {
+        self.distance_to(target)?;
+
+        let mut cells = vec!(target);
+        let mut prev = self.reached_from[(target.0 as usize, target.1 as usize)];
+
+        while let Some(coords) = prev {
+            cells.push(coords);
+            prev = self.passed_from[(coords.0 as usize, coords.1 as usize)];
+        }
+
+        cells.reverse();
+        debug_assert_eq!(cells[0], self.source);
+
+        let next_moves = cells.windows(2).map(|step| move_between(&self.topology, step[0], step[1]));
+
+        Some(cells.iter()
+            .zip(next_moves.chain(std::iter::once(None)))
+            .map(|(coords, next_move)| PathNode { coords: *coords, next_move })
+            .collect())
+    }
+
+}
+
+
+type FieldStep = ((u32, u32), u32, Option<(u32, u32)>);
+
+/// Finds the shortest distance from a source cell (x,y) to every cell on the board.
+pub fn distance_field(map: &Map, source: (u32, u32)) -> DistanceField {
+
+    let (width, height) = (map.width as usize, map.height as usize);
+
+    let mut field = DistanceField {
+        source,
+        topology: *map.topology(),
+        dist: TwoDimensionalMap::new(width, height),
+        reached_from: TwoDimensionalMap::new(width, height),
+        passed_from: TwoDimensionalMap::new(width, height),
+    };
+
+    // Queue entries are a cell, its distance from the source, and the cell we came from
+    let mut q : Queue<FieldStep> = Queue::new();
+    let mut traversed : HashSet<(u32, u32)> = HashSet::new();
+
+    q.add((source, 0, None)).unwrap();
+
+    while q.size() > 0 {
+
+        let (coords, dist, prev) = q.remove().unwrap();
+        let cell = (coords.0 as usize, coords.1 as usize);
+
+        if traversed.contains(&coords) {
+            continue;
+        }
+
+        // The first time we get to a cell is the soonest we could get there, even if
+        // we can't carry on through it yet
+        if field.dist[cell].is_none() {
+            field.dist[cell] = Some(dist);
+            field.reached_from[cell] = prev;
+        }
+
+        if !map.is_passable(coords, dist) && coords != source {
+            continue;
+        }
+
+        traversed.insert(coords);
+        field.passed_from[cell] = prev;
+
+        for next in next_cells(map, coords, dist) {
+            q.add((next, dist + 1, Some(coords))).unwrap();
+        }
+    }
+
+    field
 }
 
 
@@ -95,7 +248,8 @@ pub fn shortest_path_to(map: &Map, start: (u32, u32), target: (u32, u32)) -> Opt
 /// None.
 /// 
 /// Panics if elements can't be added to the queue (e.g. due to a lack of memory resources).
-fn bfs_to(map: &Map, start_coords: (u32, u32), target_coords: (u32, u32)) -> Option<Rc<BfsNode>> {
+fn bfs_to(map: &Map, start_coords: (u32, u32), target_coords: (u32, u32), turns: u32)
+    -> Option<Rc<BfsNode>> {
 
     let mut q : Queue<Rc<BfsNode>> = Queue::new();
     let mut traversed : HashSet<(u32, u32)> = HashSet::new();
@@ -112,74 +266,158 @@ fn bfs_to(map: &Map, start_coords: (u32, u32), target_coords: (u32, u32)) -> Opt
 
         let cur_node = q.remove().unwrap();
 
-        let x = (*cur_node).x;
-        let y = (*cur_node).y;
+        let x = cur_node.x;
+        let y = cur_node.y;
         
         // Have we seen this node before?
         if traversed.contains(&(x, y)) {
             continue;
         }
 
-        // Now we have!
-        traversed.insert((x, y));
-
         // Have we found our target node?
         if x == target_coords.0 && y == target_coords.1 {
             return Some(cur_node);
         }
 
-        // If the node is not safe, don't examine its neighbors. Source and target
-        // nodes may be unsafe, but we may not traverse any other unsafe nodes.
-        if !map.is_safe_node((x, y)) && (x, y) != start_coords {
+        // If the node can't be entered by the time we get there, don't examine its
+        // neighbors. It may be free later on though, so we might come back to it along
+        // a longer path. Source and target nodes may be unsafe, but we may not traverse
+        // any other unsafe nodes.
+        if !map.is_passable((x, y), turns + cur_node.dist) && (x, y) != start_coords {
             continue;
         }
 
-        // Examine neighbors, and add to the queue if they are in bounds
-        if x + 1 < map.width {
-            // Right
+        // Now we have!
+        traversed.insert((x, y));
+
+        // Examine neighbors, and add to the queue.
+        for (next_x, next_y) in next_cells(map, (x, y), turns + cur_node.dist) {
             q.add(Rc::new(BfsNode{
-                x: x + 1,
-                y: y,
-                dist: (*cur_node).dist + 1,
+                x: next_x,
+                y: next_y,
+                dist: cur_node.dist + 1,
                 prev: Some(Rc::clone(&cur_node))
             })).unwrap();
         }
+    }
 
-        if x != 0 {
-            // Left
-            q.add(Rc::new(BfsNode{
-                x: x - 1,
-                y: y,
-                dist: (*cur_node).dist + 1,
-                prev: Some(Rc::clone(&cur_node))
-            })).unwrap();
+    // If we reached the end of the loop, target node is inaccessible
+    None
+}
+
+
+/// Entry in the A* open set. Entries come out of the heap cheapest estimate first, with
+/// ties going to the entry furthest along its path.
+struct AStarEntry {
+    /// Cost so far plus the estimated cost of the rest of the path.
+    estimate: u32,
+
+    /// Cost of the path to this node.
+    cost: u32,
+
+    node: Rc<BfsNode>,
+}
+
+impl PartialEq for AStarEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.estimate == other.estimate && self.cost == other.cost
+    }
+}
+
+impl Eq for AStarEntry {}
+
+impl PartialOrd for AStarEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for AStarEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // BinaryHeap is a max-heap, so lower estimates need to compare greater
+        other.estimate.cmp(&self.estimate).then_with(|| self.cost.cmp(&other.cost))
+    }
+}
+
+
+/// Performs an A* search for the cheapest path from a source node (x,y) to a target node
+/// (x,y), using the distance to the target with nothing in the way as a heuristic.
+///
+/// Cells are entered under the same rules as `bfs_to`. Returns Some(BfsNode) for the
+/// target node if it's accessible from the start node, otherwise None.
+fn a_star_to<F>(map: &Map, start_coords: (u32, u32), target_coords: (u32, u32), cost: F)
+    -> Option<Rc<BfsNode>> where F: Fn((u32, u32)) -> u32 {
+
+    let mut open : BinaryHeap<AStarEntry> = BinaryHeap::new();
+    let mut traversed : HashSet<(u32, u32)> = HashSet::new();
+
+    open.push(AStarEntry {
+        estimate: map.topology().distance(start_coords, target_coords),
+        cost: 0,
+        node: Rc::new(BfsNode{
+            dist: 0,
+            prev: None,
+            x: start_coords.0,
+            y: start_coords.1,
+        }),
+    });
+
+    while let Some(AStarEntry { cost: cur_cost, node: cur_node, .. }) = open.pop() {
+
+        let coords = (cur_node.x, cur_node.y);
+
+        if traversed.contains(&coords) {
+            continue;
         }
 
-        if y != 0 {
-            // Up
-            q.add(Rc::new(BfsNode{
-                x: x,
-                y: y - 1,
-                dist: (*cur_node).dist + 1,
-                prev: Some(Rc::clone(&cur_node))
-            })).unwrap();
+        if coords == target_coords {
+            return Some(cur_node);
         }
 
-        if y + 1 < map.height {
-            // Down
-            q.add(Rc::new(BfsNode{
-                x: x,
-                y: y + 1,
-                dist: (*cur_node).dist + 1,
-                prev: Some(Rc::clone(&cur_node))
-            })).unwrap();
+        if !map.is_passable(coords, cur_node.dist) && coords != start_coords {
+            continue;
+        }
+
+        traversed.insert(coords);
+
+        for next in next_cells(map, coords, cur_node.dist) {
+            if traversed.contains(&next) {
+                continue;
+            }
+
+            let next_cost = cur_cost + std::cmp::max(cost(next), 1);
+
+            open.push(AStarEntry {
+                estimate: next_cost + map.topology().distance(next, target_coords),
+                cost: next_cost,
+                node: Rc::new(BfsNode{
+                    x: next.0,
+                    y: next.1,
+                    dist: cur_node.dist + 1,
+                    prev: Some(Rc::clone(&cur_node))
+                }),
+            });
         }
     }
 
-    // If we reached the end of the loop, target node is inaccessible
     None
 }
 
+
+/// Cells a search can step to from the given cell, `dist` steps into the search. When
+/// stepping out from our own head, stays clear of cells where we could lose a head-to-head
+/// collision, and tries cells where we could win one first.
+fn next_cells(map: &Map, coords: (u32, u32), dist: u32) -> Vec<(u32, u32)> {
+    let mut neighbours = map.neighbours(coords);
+
+    if dist == 0 && coords == map.head() {
+        neighbours.retain(|coords| map.contest_at(coords.0, coords.1) != Contest::Dangerous);
+        neighbours.sort_by_key(|coords| map.contest_at(coords.0, coords.1));
+    }
+
+    neighbours
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -241,10 +479,11 @@ mod tests {
         //   - S - - - -
         //   - S - - - -
         //   T S - - - -
-        //   - S Y - - -
+        //   S S Y - - -
         //
         // The BFS algorithm should determine that that target node is inaccessible
-        // from the source.
+        // from the source. The snake's tail is tucked in next to the target, so no part
+        // of the wall moves out of the way before we could get there.
 
         let map = Map::new(&SnakeConfig {
             board: Board {
@@ -258,6 +497,7 @@ mod tests {
                             Coords { x: 1, y: 2 },
                             Coords { x: 1, y: 3 },
                             Coords { x: 1, y: 4 },
+                            Coords { x: 0, y: 4 },
                         ),
                         ..Default::default()
                     }
@@ -296,6 +536,48 @@ mod tests {
         assert!(path[1].next_move.is_none());
     }
 
+    #[test]
+    fn paths_wrap_around_wrapped_boards() {
+        // Board state:
+        //   - - - - - -
+        //   Y S S S - T
+        //   - - - - - -
+        //
+        // On a wrapped board, the target is one step to our left.
+        let you = Snake {
+            body: vec!(Coords { x: 0, y: 1 }, Coords { x: 1, y: 1 }, Coords { x: 2, y: 1 }, Coords { x: 3, y: 1 }),
+            ..Default::default()
+        };
+        let mut config = SnakeConfig {
+            board: Board {
+                width: 6,
+                height: 3,
+                snakes: vec!(you.clone()),
+                ..Default::default()
+            },
+            you,
+            ..Default::default()
+        };
+
+        let map = Map::new(&config);
+        assert_eq!(shortest_path_to(&map, (0, 1), (5, 1)).unwrap().len(), 8);
+
+        config.game.ruleset.name = String::from("wrapped");
+        let map = Map::new(&config);
+        assert_eq!(map.topology().distance((0, 1), (5, 1)), 1);
+
+        let path = shortest_path_to(&map, (0, 1), (5, 1)).unwrap();
+        assert_eq!(path.len(), 2);
+        assert_eq!(path[0].next_move, Some(Move::Left));
+
+        let path = cheapest_path_to(&map, (0, 1), (5, 1), cautious_cost(&map)).unwrap();
+        assert_eq!(path.len(), 2);
+
+        let field = distance_field(&map, (0, 1));
+        assert_eq!(field.distance_to((5, 1)), Some(1));
+        assert_eq!(field.distance_to((5, 0)), Some(2));
+    }
+
     #[test]
     fn bfs_handles_no_path() {
         // In the strange case where the source and target node are the
@@ -319,6 +601,121 @@ mod tests {
         assert!(path[0].next_move.is_none());
     }
 
+    #[test]
+    fn bfs_passes_through_cells_that_will_be_free() {
+        // Board state:
+        //   Y - S S
+        //   - - - S
+        //   - - - T
+        //
+        // The snake blocks the way to the target (T) right now, but its tail end will have
+        // moved on by the time we get there.
+        let map = Map::new(&SnakeConfig {
+            board: Board {
+                width: 4,
+                height: 3,
+                snakes: vec!(
+                    Snake {
+                        id: String::from("enemy"),
+                        body: vec!(
+                            Coords { x: 2, y: 0 },
+                            Coords { x: 3, y: 0 },
+                            Coords { x: 3, y: 1 },
+                            Coords { x: 3, y: 2 },
+                        ),
+                        ..Default::default()
+                    }
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        let path = shortest_path_to(&map, (0, 0), (3, 2));
+        assert!(path.is_some());
+        assert_eq!(path.unwrap().len(), 6);
+
+        // Going through the middle of the snake is still off limits
+        let path = shortest_path_to(&map, (0, 0), (3, 0)).unwrap();
+        assert!(path.len() > 5);
+    }
+
+    #[test]
+    fn bfs_can_set_off_later() {
+        // Board state:
+        //   - - S -
+        //   - Y S T
+        //   - - S -
+        //
+        // The snake's tail is stacked up, so the way across won't be clear for a few
+        // turns.
+        let map = Map::new(&SnakeConfig {
+            board: Board {
+                width: 4,
+                height: 3,
+                snakes: vec!(
+                    Snake {
+                        id: String::from("enemy"),
+                        body: vec!(
+                            Coords { x: 2, y: 0 },
+                            Coords { x: 2, y: 1 },
+                            Coords { x: 2, y: 2 },
+                            Coords { x: 2, y: 2 },
+                            Coords { x: 2, y: 2 },
+                        ),
+                        ..Default::default()
+                    }
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        assert!(shortest_path_to(&map, (1, 1), (3, 1)).is_none());
+
+        let path = shortest_path_after(&map, (1, 1), (3, 1), 2).unwrap();
+        assert_eq!(path.len(), 5);
+        assert_eq!(path[1].coords, (1, 2));
+    }
+
+    #[test]
+    fn bfs_avoids_losing_head_to_head() {
+        // - - - -
+        // Y - E -
+        // S - E -
+        // S - E -
+        //
+        // The enemy is as long as us, so we shouldn't step right towards it on our way
+        // to the bottom right.
+        let you = Snake {
+            id: String::from("you"),
+            body: vec!(Coords { x: 0, y: 1 }, Coords { x: 0, y: 2 }, Coords { x: 0, y: 3 }),
+            ..Default::default()
+        };
+
+        let map = Map::new(&SnakeConfig {
+            board: Board {
+                width: 4,
+                height: 4,
+                snakes: vec!(
+                    you.clone(),
+                    Snake {
+                        id: String::from("enemy"),
+                        body: vec!(Coords { x: 2, y: 1 }, Coords { x: 2, y: 2 }, Coords { x: 2, y: 3 }),
+                        ..Default::default()
+                    }
+                ),
+                ..Default::default()
+            },
+            you,
+            ..Default::default()
+        });
+
+        let path = shortest_path_to(&map, (0, 1), (3, 3)).unwrap();
+        assert_eq!(path[0].next_move, Some(Move::Up));
+        assert_eq!(path.len(), 8);
+    }
+
     #[test]
     fn bfs_finds_path_to_unsafe_node() {
         // S S - - -
@@ -359,4 +756,216 @@ mod tests {
         assert!(path[2].next_move.is_none());
     }
 
+    #[test]
+    fn cheapest_path_matches_bfs_with_unit_costs() {
+        // Same board as bfs_finds_target_node
+        let map = Map::new(&SnakeConfig {
+            board: Board{
+                width: 6,
+                height: 5,
+                snakes: vec!(
+                    Snake {
+                        body: vec!(
+                            Coords { x: 1, y: 0 },
+                            Coords { x: 1, y: 1 },
+                            Coords { x: 1, y: 2 },
+                            Coords { x: 1, y: 3 },
+                        ),
+                        ..Default::default()
+                    }
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        let path = cheapest_path_to(&map, (2, 4), (0, 3), |_| 1).unwrap();
+        let coords: Vec<(u32, u32)> = path.iter().map(|node| node.coords).collect();
+        assert_eq!(coords, vec!((2, 4), (1, 4), (0, 4), (0, 3)));
+    }
+
+    #[test]
+    fn cheapest_path_avoids_expensive_cells() {
+        // Board state:
+        //   Y - - - T
+        //   - - - - -
+        //   - - - - -
+        //
+        // The top row is expensive, so the cheapest path drops down a row to get across.
+        let map = Map::new(&SnakeConfig {
+            board: Board {
+                width: 5,
+                height: 3,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        let cost = |(_, y): (u32, u32)| if y == 0 { 10 } else { 1 };
+        let path = cheapest_path_to(&map, (0, 0), (4, 0), cost).unwrap();
+
+        assert_eq!(path.len(), 7);
+        assert_eq!(path[0].next_move, Some(Move::Down));
+        assert!(path[1..6].iter().all(|node| node.coords.1 == 1));
+    }
+
+    #[test]
+    fn cautious_cost_penalises_edges_and_enemy_heads() {
+        // Board state:
+        //   - - - - -
+        //   - - - - -
+        //   - - E - -
+        //   - - E - -
+        let map = Map::new(&SnakeConfig {
+            board: Board {
+                width: 5,
+                height: 4,
+                snakes: vec!(
+                    Snake {
+                        id: String::from("enemy"),
+                        body: vec!(Coords { x: 2, y: 2 }, Coords { x: 2, y: 3 }),
+                        ..Default::default()
+                    }
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        let cost = cautious_cost(&map);
+        assert_eq!(cost((1, 1)), 1);
+        assert!(cost((0, 1)) > cost((1, 1)));
+        assert!(cost((1, 2)) > cost((0, 1)));
+    }
+
+    #[test]
+    fn distance_field_matches_shortest_paths() {
+        // Board state:
+        //   Y - S S
+        //   - - - S
+        //   - - - T
+        //
+        // Same board as bfs_passes_through_cells_that_will_be_free.
+        let map = Map::new(&SnakeConfig {
+            board: Board {
+                width: 4,
+                height: 3,
+                snakes: vec!(
+                    Snake {
+                        id: String::from("enemy"),
+                        body: vec!(
+                            Coords { x: 2, y: 0 },
+                            Coords { x: 3, y: 0 },
+                            Coords { x: 3, y: 1 },
+                            Coords { x: 3, y: 2 },
+                        ),
+                        ..Default::default()
+                    }
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        let field = distance_field(&map, (0, 0));
+
+        for x in 0..4 {
+            for y in 0..3 {
+                let path = shortest_path_to(&map, (0, 0), (x, y)).unwrap();
+                assert_eq!(field.distance_to((x, y)), Some(path.len() as u32 - 1));
+
+                // Paths must be walkable, one move at a time, from the source to the cell
+                let path = field.path_to((x, y)).unwrap();
+                assert_eq!(path[0].coords, (0, 0));
+                assert_eq!(path[path.len() - 1].coords, (x, y));
+                assert!(path[path.len() - 1].next_move.is_none());
+
+                for step in path.windows(2) {
+                    let next_move = step[0].next_move.unwrap();
+                    assert_eq!(move_between(map.topology(), step[0].coords, step[1].coords), Some(next_move));
+                    assert_eq!(map.topology().distance(step[0].coords, step[1].coords), 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn distance_field_paths_through_vacated_cells() {
+        // Board state:
+        //   - S - -
+        //   X S - -
+        //   - S S -
+        //
+        // The middle of the wall is one move away from X, but it's still blocked then,
+        // so paths through it have to wait until the tail end has moved on.
+        let map = Map::new(&SnakeConfig {
+            board: Board {
+                width: 4,
+                height: 3,
+                snakes: vec!(
+                    Snake {
+                        id: String::from("enemy"),
+                        body: vec!(
+                            Coords { x: 2, y: 2 },
+                            Coords { x: 1, y: 2 },
+                            Coords { x: 1, y: 1 },
+                            Coords { x: 1, y: 0 },
+                        ),
+                        ..Default::default()
+                    }
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        let field = distance_field(&map, (0, 1));
+        assert_eq!(field.distance_to((1, 1)), Some(1));
+        assert_eq!(field.distance_to((2, 1)), Some(4));
+
+        let path = field.path_to((2, 1)).unwrap();
+        assert_eq!(path.len(), 5);
+        for (turns, node) in path.iter().enumerate().skip(1) {
+            assert!(map.is_passable(node.coords, turns as u32));
+        }
+    }
+
+    #[test]
+    fn distance_field_leaves_unreachable_cells_empty() {
+        // Board state:
+        //   - H - - - -
+        //   - S - - - -
+        //   - S - - - -
+        //   T S - - - -
+        //   S S Y - - -
+        //
+        // Same board as bfs_determines_target_node_inaccessible.
+        let map = Map::new(&SnakeConfig {
+            board: Board {
+                width: 6,
+                height: 5,
+                snakes: vec!(
+                    Snake {
+                        body: vec!(
+                            Coords { x: 1, y: 0 },
+                            Coords { x: 1, y: 1 },
+                            Coords { x: 1, y: 2 },
+                            Coords { x: 1, y: 3 },
+                            Coords { x: 1, y: 4 },
+                            Coords { x: 0, y: 4 },
+                        ),
+                        ..Default::default()
+                    }
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        let field = distance_field(&map, (2, 4));
+        assert_eq!(field.distance_to((0, 3)), None);
+        assert!(field.path_to((0, 3)).is_none());
+        assert_eq!(field.distance_to((5, 0)), Some(7));
+    }
+
 }
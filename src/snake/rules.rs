@@ -0,0 +1,941 @@
+//
+// Local game engine, playing by the rules of each game mode.
+// See https://docs.battlesnake.com/references/rules
+//
+// Boards use this crate's coordinates, where rows count down from the top and moving up
+// takes a snake from row y to row y - 1. The official API version 1 engine counts rows up
+// from the bottom, so boards need flipping (see `SnakeConfig::flip_vertically`) before
+// they can be compared with its games.
+//
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use super::api::{SnakeConfig, Board, Snake, Coords, Ruleset, RulesetSettings, RoyaleSettings, SquadSettings};
+use super::topology::{Shape, Topology};
+use super::utils::Move;
+
+/// Health a snake has at the start of the game, and after eating.
+pub const MAX_HEALTH: u8 = 100;
+
+/// Length of a snake at the start of the game.
+pub const START_LENGTH: usize = 3;
+
+/// Why a snake was removed from the game.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum EliminationCause {
+    OutOfHealth,
+    OutOfBounds,
+    SelfCollision,
+    BodyCollision,
+    HeadToHeadCollision,
+    /// A squadmate was eliminated, and took the rest of its squad with it.
+    SquadEliminated,
+}
+
+#[derive(Debug, Clone)]
+pub struct Elimination {
+    /// ID of the eliminated snake.
+    pub id: String,
+    pub cause: EliminationCause,
+    /// ID of the snake responsible for the elimination, if any.
+    pub by: Option<String>,
+}
+
+/// Game modes, each with its own twist on the standard rules.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum Mode {
+    #[default]
+    Standard,
+    /// A single snake, surviving for as long as it can.
+    Solo,
+    /// Hazards close in from the edges of the board as the game goes on.
+    Royale,
+    /// There's no food, but snakes grow every turn and never go hungry.
+    Constrictor,
+    /// Snakes moving off one edge of the board come back on at the opposite edge.
+    Wrapped,
+    /// Snakes play in squads, and can share their health, length and fate.
+    Squad,
+}
+
+impl Mode {
+
+    /// Mode of the ruleset with the given name. Rulesets we don't know are played like
+    /// standard games.
+    pub fn from_name(name: &str) -> Mode {
+        match name {
+            "solo" => Mode::Solo,
+            "royale" => Mode::Royale,
+            "constrictor" => Mode::Constrictor,
+            "wrapped" => Mode::Wrapped,
+            "squad" => Mode::Squad,
+            _ => Mode::Standard,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Standard => "standard",
+            Mode::Solo => "solo",
+            Mode::Royale => "royale",
+            Mode::Constrictor => "constrictor",
+            Mode::Wrapped => "wrapped",
+            Mode::Squad => "squad",
+        }
+    }
+
+}
+
+#[derive(Clone, Debug)]
+pub struct Rules {
+    /// Percent chance of a new piece of food spawning each turn.
+    pub food_spawn_chance: u32,
+
+    /// Minimum amount of food on the board. Food is spawned at the end of a turn until
+    /// there is at least this much.
+    pub minimum_food: u32,
+
+    pub mode: Mode,
+
+    /// Health lost each turn by snakes with their heads in a hazard, on top of the usual
+    /// one.
+    pub hazard_damage: u32,
+
+    /// Number of turns between the hazards closing in, in royale games. Hazards never
+    /// close in if this is zero.
+    pub shrink_every: u32,
+
+    /// How squadmates play together, in squad games.
+    pub squad: SquadSettings,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules::from_ruleset(&Default::default())
+    }
+}
+
+/// Move made by a snake that doesn't pick one: keep going the way it was going, or up if
+/// it hasn't moved yet.
+pub fn default_move(snake: &Snake) -> Move {
+    if snake.body.len() < 2 {
+        return Move::Up;
+    }
+
+    let head = snake.body[0];
+    let neck = snake.body[1];
+
+    if head.x > neck.x {
+        Move::Right
+    }
+    else if head.x < neck.x {
+        Move::Left
+    }
+    else if head.y > neck.y {
+        Move::Down
+    }
+    else {
+        // Either moving up, or the head is stacked on its neck
+        Move::Up
+    }
+}
+
+/// Whether two snakes are on the same side: the same snake, or squadmates.
+pub fn same_squad(a: &Snake, b: &Snake) -> bool {
+    a.id == b.id || (!a.squad.is_empty() && a.squad == b.squad)
+}
+
+impl Rules {
+
+    /// Rules set up the way the given ruleset describes.
+    pub fn from_ruleset(ruleset: &Ruleset) -> Rules {
+        let mode = Mode::from_name(&ruleset.name);
+        let settings = &ruleset.settings;
+
+        // Constrictor snakes grow without eating, so there's no food
+        let (food_spawn_chance, minimum_food) = match mode {
+            Mode::Constrictor => (0, 0),
+            _ => (settings.food_spawn_chance, settings.minimum_food),
+        };
+
+        Rules {
+            food_spawn_chance,
+            minimum_food,
+            mode,
+            hazard_damage: settings.hazard_damage_per_turn,
+            shrink_every: settings.royale.shrink_every_n_turns,
+            squad: settings.squad,
+        }
+    }
+
+    /// Rules for looking ahead in a game played under `ruleset`. Food never spawns and
+    /// hazards never close in, since there's no way of knowing where they will.
+    pub fn for_search(ruleset: &Ruleset) -> Rules {
+        Rules {
+            food_spawn_chance: 0,
+            minimum_food: 0,
+            shrink_every: 0,
+            ..Rules::from_ruleset(ruleset)
+        }
+    }
+
+    /// Ruleset describing these rules to players.
+    pub fn ruleset(&self) -> Ruleset {
+        Ruleset {
+            name: String::from(self.mode.name()),
+            version: String::from(env!("CARGO_PKG_VERSION")),
+            settings: RulesetSettings {
+                food_spawn_chance: self.food_spawn_chance,
+                minimum_food: self.minimum_food,
+                hazard_damage_per_turn: self.hazard_damage,
+                royale: RoyaleSettings { shrink_every_n_turns: self.shrink_every },
+                squad: self.squad,
+            },
+        }
+    }
+
+    /// Whether a game is over. Solo games go on until the snake is eliminated, squad games
+    /// until only one squad is left, and other games until only one snake is left.
+    pub fn is_over(&self, config: &SnakeConfig) -> bool {
+        let snakes = &config.board.snakes;
+
+        match self.mode {
+            Mode::Solo => snakes.is_empty(),
+            Mode::Squad => snakes.iter().all(|snake| same_squad(snake, &snakes[0])),
+            _ => snakes.len() < 2,
+        }
+    }
+
+    /// Sets up a board for the start of a game. `snakes` are (id, name) pairs.
+    ///
+    /// Snakes start in the corners and then the middle of the edges of boards that are
+    /// at least 7x7, or on random squares otherwise. Each snake gets a piece of food next
+    /// to it, with another in the middle of the board, unless there's no food in the mode
+    /// being played. In squad games, snakes are split between two squads, taking turns.
+    ///
+    /// Panics if there isn't room for every snake.
+    pub fn create_board<R: Rng>(&self, width: u32, height: u32, snakes: &[(String, String)], rng: &mut R)
+        -> Board {
+
+        let mut starts = if width >= 7 && height >= 7 && snakes.len() <= 8 {
+            let (min_x, mid_x, max_x) = (1, (width - 1) / 2, width - 2);
+            let (min_y, mid_y, max_y) = (1, (height - 1) / 2, height - 2);
+
+            let mut corners = vec!(
+                Coords { x: min_x, y: min_y },
+                Coords { x: max_x, y: min_y },
+                Coords { x: min_x, y: max_y },
+                Coords { x: max_x, y: max_y },
+            );
+            let mut edges = vec!(
+                Coords { x: mid_x, y: min_y },
+                Coords { x: min_x, y: mid_y },
+                Coords { x: max_x, y: mid_y },
+                Coords { x: mid_x, y: max_y },
+            );
+            corners.shuffle(rng);
+            edges.shuffle(rng);
+
+            corners.extend(edges);
+            corners
+        }
+        else {
+            // Sticking to squares of one colour (on a chess board) keeps snakes from
+            // starting right next to each other
+            let mut squares: Vec<Coords> = (0..width)
+                .flat_map(|x| (0..height).map(move |y| Coords { x, y }))
+                .filter(|coords| (coords.x + coords.y) % 2 == 0)
+                .collect();
+            squares.shuffle(rng);
+            squares
+        };
+
+        assert!(starts.len() >= snakes.len(), "No room for {} snakes", snakes.len());
+        starts.truncate(snakes.len());
+
+        let mut board = Board {
+            width,
+            height,
+            snakes: snakes.iter().zip(starts.iter()).enumerate().map(|(idx, ((id, name), &start))| {
+                Snake {
+                    id: id.clone(),
+                    name: name.clone(),
+                    health: MAX_HEALTH,
+                    body: vec!(start; START_LENGTH),
+                    shout: String::new(),
+                    head: start,
+                    length: START_LENGTH as u32,
+                    squad: match self.mode {
+                        Mode::Squad => format!("squad-{}", idx % 2),
+                        _ => String::new(),
+                    },
+                    ..Default::default()
+                }
+            }).collect(),
+            ..Default::default()
+        };
+
+        if self.mode == Mode::Constrictor {
+            return board;
+        }
+
+        let center = Coords { x: (width - 1) / 2, y: (height - 1) / 2 };
+        let is_free = |board: &Board, coords: &Coords| {
+            coords.x < width && coords.y < height && *coords != center
+                && !board.food.contains(coords)
+                && !board.snakes.iter().any(|snake| snake.body.contains(coords))
+        };
+
+        for start in starts.iter() {
+            let diagonals: Vec<Coords> = [(-1, -1), (-1, 1), (1, -1), (1, 1)].iter()
+                .map(|(dx, dy)| Coords {
+                    x: (start.x as i64 + dx) as u32,
+                    y: (start.y as i64 + dy) as u32,
+                })
+                .filter(|coords| is_free(&board, coords))
+                .collect();
+
+            if let Some(food) = diagonals.choose(rng) {
+                board.food.push(*food);
+            }
+        }
+
+        if !board.snakes.iter().any(|snake| snake.body.contains(&center)) {
+            board.food.push(center);
+        }
+
+        board
+    }
+
+    /// Plays one turn. `moves` maps snake IDs to the move each snake makes; snakes without
+    /// a move make their `default_move`.
+    ///
+    /// Returns the state of the game at the start of the next turn, which only contains
+    /// snakes that are still alive, along with the snakes eliminated this turn. `you` is
+    /// updated to its new state even if it was eliminated.
+    pub fn next_turn<R: Rng>(&self, config: &SnakeConfig, moves: &HashMap<String, Move>, rng: &mut R)
+        -> (SnakeConfig, Vec<Elimination>) {
+
+        let mut next = config.clone();
+        next.turn += 1;
+
+        let board = &mut next.board;
+        let topology = Topology::new(board.width, board.height, Shape::of(self.mode));
+
+        // Move snakes, then reduce their health. Snakes moving off the board stay where
+        // they are, to be eliminated.
+        let mut off_board = HashSet::new();
+        for snake in board.snakes.iter_mut() {
+            let move_val = moves.get(&snake.id).copied().unwrap_or_else(|| default_move(snake));
+            let head = snake.body[0];
+
+            match topology.step((head.x, head.y), move_val) {
+                Some((x, y)) => {
+                    snake.body.pop();
+                    snake.body.insert(0, Coords { x, y });
+                },
+                None => {
+                    off_board.insert(snake.id.clone());
+                },
+            }
+            snake.health = snake.health.saturating_sub(1);
+        }
+
+        // Hazards hurt, unless there's food in them to make up for it
+        let hazard_damage = self.hazard_damage.min(MAX_HEALTH as u32) as u8;
+        for snake in board.snakes.iter_mut() {
+            let head = snake.body[0];
+            if board.hazards.contains(&head) && !board.food.contains(&head) {
+                snake.health = snake.health.saturating_sub(hazard_damage);
+            }
+        }
+
+        // Feed snakes. Every snake whose head is on a piece of food eats it.
+        let mut eaten = HashSet::new();
+        for snake in board.snakes.iter_mut() {
+            let head = snake.body[0];
+            if board.food.contains(&head) {
+                let tail = snake.body[snake.body.len() - 1];
+                snake.body.push(tail);
+                snake.health = MAX_HEALTH;
+                eaten.insert((head.x, head.y));
+            }
+        }
+        board.food.retain(|food| !eaten.contains(&(food.x, food.y)));
+
+        if self.mode == Mode::Constrictor {
+            for snake in board.snakes.iter_mut() {
+                let tail = snake.body[snake.body.len() - 1];
+                snake.body.push(tail);
+                snake.health = MAX_HEALTH;
+            }
+        }
+
+        self.spawn_food(&mut next, rng);
+
+        if self.mode == Mode::Royale && self.shrink_every > 0 && next.turn % self.shrink_every == 0 {
+            close_in(&mut next.board, rng);
+        }
+
+        let eliminations = eliminate(self, &next, &off_board);
+        let eliminated: HashSet<&str> = eliminations.iter().map(|e| e.id.as_str()).collect();
+
+        // Keep `you` up to date, even if it won't be on the board any more
+        if let Some(you) = next.board.snakes.iter().find(|snake| snake.id == next.you.id) {
+            next.you = you.clone();
+        }
+
+        next.board.snakes.retain(|snake| !eliminated.contains(snake.id.as_str()));
+
+        if self.mode == Mode::Squad {
+            self.share_between_squadmates(&mut next.board);
+        }
+
+        for snake in next.board.snakes.iter_mut().chain(std::iter::once(&mut next.you)) {
+            snake.head = snake.body[0];
+            snake.length = snake.body.len() as u32;
+        }
+
+        (next, eliminations)
+    }
+
+    /// Tops the board up to the minimum amount of food, or spawns a new piece of food by
+    /// chance if there's already enough.
+    fn spawn_food<R: Rng>(&self, config: &mut SnakeConfig, rng: &mut R) {
+        let board = &mut config.board;
+
+        let to_spawn = if (board.food.len() as u32) < self.minimum_food {
+            self.minimum_food - board.food.len() as u32
+        }
+        else if self.food_spawn_chance > 0 && rng.gen_range(0, 100) < self.food_spawn_chance {
+            1
+        }
+        else {
+            0
+        };
+
+        for _ in 0..to_spawn {
+            let mut free = Vec::new();
+            for x in 0..board.width {
+                for y in 0..board.height {
+                    let coords = Coords { x, y };
+                    let occupied = board.food.contains(&coords)
+                        || board.snakes.iter().any(|snake| snake.body.contains(&coords));
+
+                    if !occupied {
+                        free.push(coords);
+                    }
+                }
+            }
+
+            if free.is_empty() {
+                return;
+            }
+
+            let idx = rng.gen_range(0, free.len());
+            board.food.push(free[idx]);
+        }
+    }
+
+    /// Gives squadmates the health of the healthiest of them, and the length of the
+    /// longest, if they're set up to share them.
+    fn share_between_squadmates(&self, board: &mut Board) {
+        let snakes = board.snakes.clone();
+
+        for snake in board.snakes.iter_mut() {
+            let squad: Vec<&Snake> = snakes.iter().filter(|other| same_squad(snake, other)).collect();
+
+            if self.squad.shared_health {
+                snake.health = squad.iter().map(|other| other.health).max().unwrap();
+            }
+
+            if self.squad.shared_length {
+                let length = squad.iter().map(|other| other.body.len()).max().unwrap();
+                let tail = snake.body[snake.body.len() - 1];
+                snake.body.resize(length, tail);
+            }
+        }
+    }
+
+}
+
+/// Closes the hazards in on the rest of the board by a row or column, from a random side.
+fn close_in<R: Rng>(board: &mut Board, rng: &mut R) {
+    let safe: Vec<Coords> = (0..board.width)
+        .flat_map(|x| (0..board.height).map(move |y| Coords { x, y }))
+        .filter(|coords| !board.hazards.contains(coords))
+        .collect();
+
+    if safe.is_empty() {
+        return;
+    }
+
+    let min_x = safe.iter().map(|coords| coords.x).min().unwrap();
+    let max_x = safe.iter().map(|coords| coords.x).max().unwrap();
+    let min_y = safe.iter().map(|coords| coords.y).min().unwrap();
+    let max_y = safe.iter().map(|coords| coords.y).max().unwrap();
+
+    let side = rng.gen_range(0, 4);
+    board.hazards.extend(safe.into_iter().filter(|coords| match side {
+        0 => coords.x == min_x,
+        1 => coords.x == max_x,
+        2 => coords.y == min_y,
+        _ => coords.y == max_y,
+    }));
+}
+
+/// Works out which snakes on the board have died after moving. `off_board` holds the IDs
+/// of snakes that tried to move off the board.
+fn eliminate(rules: &Rules, config: &SnakeConfig, off_board: &HashSet<String>) -> Vec<Elimination> {
+    let board = &config.board;
+    let mut eliminations = Vec::new();
+
+    // Starved and out of bounds snakes are removed before collisions are checked, so
+    // they can't take anyone down with them.
+    for snake in board.snakes.iter() {
+        let cause = if snake.health == 0 {
+            Some(EliminationCause::OutOfHealth)
+        }
+        else if off_board.contains(&snake.id) {
+            Some(EliminationCause::OutOfBounds)
+        }
+        else {
+            None
+        };
+
+        if let Some(cause) = cause {
+            eliminations.push(Elimination { id: snake.id.clone(), cause, by: None });
+        }
+    }
+
+    let remaining: Vec<&Snake> = board.snakes.iter()
+        .filter(|snake| !eliminations.iter().any(|e| e.id == snake.id))
+        .collect();
+
+    // Collisions are all decided at once, so every snake in a pile-up is eliminated
+    let mut collisions = Vec::new();
+    for snake in remaining.iter() {
+        let head = snake.body[0];
+
+        if snake.body[1..].contains(&head) {
+            collisions.push(Elimination {
+                id: snake.id.clone(),
+                cause: EliminationCause::SelfCollision,
+                by: None,
+            });
+            continue;
+        }
+
+        // Squadmates can be set up to move through each other
+        let passes_through = |other: &Snake| {
+            rules.mode == Mode::Squad && rules.squad.allow_body_collisions && same_squad(snake, other)
+        };
+
+        let body_hit = remaining.iter()
+            .find(|other| other.id != snake.id && !passes_through(other) && other.body[1..].contains(&head));
+
+        if let Some(other) = body_hit {
+            collisions.push(Elimination {
+                id: snake.id.clone(),
+                cause: EliminationCause::BodyCollision,
+                by: Some(other.id.clone()),
+            });
+            continue;
+        }
+
+        // Losing a head-to-head means being no longer than the other snake
+        let head_hit = remaining.iter().find(|other| {
+            other.id != snake.id && other.body[0] == head && snake.body.len() <= other.body.len()
+        });
+
+        if let Some(other) = head_hit {
+            collisions.push(Elimination {
+                id: snake.id.clone(),
+                cause: EliminationCause::HeadToHeadCollision,
+                by: Some(other.id.clone()),
+            });
+        }
+    }
+
+    eliminations.extend(collisions);
+
+    // Snakes that share their fate go down with any squadmate that does
+    if rules.mode == Mode::Squad && rules.squad.shared_elimination {
+        let mut squadmates: Vec<Elimination> = Vec::new();
+
+        for elimination in eliminations.iter() {
+            let snake = board.snakes.iter().find(|snake| snake.id == elimination.id).unwrap();
+
+            for other in board.snakes.iter().filter(|other| same_squad(snake, other)) {
+                let eliminated = eliminations.iter().chain(squadmates.iter()).any(|e| e.id == other.id);
+
+                if !eliminated {
+                    squadmates.push(Elimination {
+                        id: other.id.clone(),
+                        cause: EliminationCause::SquadEliminated,
+                        by: Some(snake.id.clone()),
+                    });
+                }
+            }
+        }
+
+        eliminations.extend(squadmates);
+    }
+
+    eliminations
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::snake::api::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    /// Rules that never spawn food, so boards stay predictable.
+    fn no_food() -> Rules {
+        Rules {
+            food_spawn_chance: 0,
+            minimum_food: 0,
+            ..Default::default()
+        }
+    }
+
+    fn rng() -> StdRng {
+        StdRng::seed_from_u64(0)
+    }
+
+    fn snake(id: &str, body: &[(u32, u32)]) -> Snake {
+        Snake {
+            id: String::from(id),
+            body: body.iter().map(|&(x, y)| Coords { x, y }).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn board(snakes: Vec<Snake>, food: &[(u32, u32)]) -> SnakeConfig {
+        SnakeConfig {
+            board: Board {
+                width: 5,
+                height: 5,
+                food: food.iter().map(|&(x, y)| Coords { x, y }).collect(),
+                snakes,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn board_with(snakes: Vec<Snake>) -> SnakeConfig {
+        board(snakes, &[])
+    }
+
+    fn moves(moves: &[(&str, Move)]) -> HashMap<String, Move> {
+        moves.iter().map(|&(id, move_val)| (String::from(id), move_val)).collect()
+    }
+
+    fn coords(snake: &Snake) -> Vec<(u32, u32)> {
+        snake.body.iter().map(|c| (c.x, c.y)).collect()
+    }
+
+    #[test]
+    fn creates_board_with_snakes_and_food() {
+        let snakes: Vec<(String, String)> = (0..4)
+            .map(|i| (format!("snake-{}", i), String::from("Snek")))
+            .collect();
+
+        let board = Rules::default().create_board(11, 11, &snakes, &mut rng());
+
+        assert_eq!(board.snakes.len(), 4);
+        for snake in board.snakes.iter() {
+            assert_eq!(snake.body.len(), START_LENGTH);
+            assert_eq!(snake.health, MAX_HEALTH);
+
+            // Everyone starts in a corner, with food nearby
+            assert!(snake.head.x == 1 || snake.head.x == 9);
+            assert!(snake.head.y == 1 || snake.head.y == 9);
+            assert!(board.food.iter().any(|food| {
+                (food.x as i64 - snake.head.x as i64).abs() == 1
+                    && (food.y as i64 - snake.head.y as i64).abs() == 1
+            }));
+        }
+
+        assert!(board.food.contains(&Coords { x: 5, y: 5 }));
+    }
+
+    #[test]
+    fn moves_snakes_and_shrinks_tails() {
+        let config = board(vec!(snake("a", &[(1, 1), (1, 2), (1, 3)])), &[]);
+        let (next, eliminations) = no_food().next_turn(&config, &moves(&[("a", Move::Right)]), &mut rng());
+
+        assert!(eliminations.is_empty());
+        assert_eq!(next.turn, 1);
+        assert_eq!(coords(&next.board.snakes[0]), vec!((2, 1), (1, 1), (1, 2)));
+        assert_eq!(next.board.snakes[0].health, 99);
+    }
+
+    #[test]
+    fn snakes_without_a_move_keep_going() {
+        let config = board(vec!(snake("a", &[(1, 2), (1, 3), (1, 4)])), &[]);
+        let (next, _) = no_food().next_turn(&config, &HashMap::new(), &mut rng());
+
+        assert_eq!(coords(&next.board.snakes[0]), vec!((1, 1), (1, 2), (1, 3)));
+    }
+
+    #[test]
+    fn eating_grows_snake_and_restores_health() {
+        let mut config = board(vec!(snake("a", &[(1, 1), (1, 2), (1, 3)])), &[(2, 1), (4, 4)]);
+        config.board.snakes[0].health = 50;
+
+        let (next, _) = no_food().next_turn(&config, &moves(&[("a", Move::Right)]), &mut rng());
+
+        let snake = &next.board.snakes[0];
+        assert_eq!(coords(snake), vec!((2, 1), (1, 1), (1, 2), (1, 2)));
+        assert_eq!(snake.health, MAX_HEALTH);
+        assert_eq!(snake.length, 4);
+        assert_eq!(next.board.food, vec!(Coords { x: 4, y: 4 }));
+    }
+
+    #[test]
+    fn eliminates_starving_and_out_of_bounds_snakes() {
+        let mut config = board(vec!(
+            snake("starving", &[(2, 2), (2, 3), (2, 4)]),
+            snake("wall", &[(0, 0), (1, 0), (2, 0)]),
+        ), &[]);
+        config.board.snakes[0].health = 1;
+
+        let (next, eliminations) = no_food().next_turn(
+            &config,
+            &moves(&[("starving", Move::Up), ("wall", Move::Left)]),
+            &mut rng()
+        );
+
+        assert!(next.board.snakes.is_empty());
+        assert_eq!(eliminations[0].id, "starving");
+        assert_eq!(eliminations[0].cause, EliminationCause::OutOfHealth);
+        assert_eq!(eliminations[1].id, "wall");
+        assert_eq!(eliminations[1].cause, EliminationCause::OutOfBounds);
+    }
+
+    #[test]
+    fn eliminates_body_collisions() {
+        // "a" runs into its own body, "b" runs into "c"
+        let config = board(vec!(
+            snake("a", &[(1, 1), (1, 2), (2, 2), (2, 1), (2, 0)]),
+            snake("b", &[(3, 3), (3, 4), (4, 4)]),
+            snake("c", &[(4, 2), (4, 3), (4, 4)]),
+        ), &[]);
+
+        let (next, eliminations) = no_food().next_turn(
+            &config,
+            &moves(&[("a", Move::Right), ("b", Move::Right), ("c", Move::Up)]),
+            &mut rng()
+        );
+
+        assert_eq!(next.board.snakes.len(), 1);
+        assert_eq!(next.board.snakes[0].id, "c");
+        assert_eq!(eliminations[0].cause, EliminationCause::SelfCollision);
+        assert_eq!(eliminations[1].cause, EliminationCause::BodyCollision);
+        assert_eq!(eliminations[1].by.as_deref(), Some("c"));
+    }
+
+    #[test]
+    fn head_to_head_eliminates_shorter_snakes() {
+        let config = board(vec!(
+            snake("long", &[(0, 2), (0, 3), (0, 4), (1, 4)]),
+            snake("short", &[(2, 2), (2, 3), (2, 4)]),
+        ), &[]);
+
+        let (next, eliminations) = no_food().next_turn(
+            &config,
+            &moves(&[("long", Move::Right), ("short", Move::Left)]),
+            &mut rng()
+        );
+
+        assert_eq!(next.board.snakes.len(), 1);
+        assert_eq!(next.board.snakes[0].id, "long");
+        assert_eq!(eliminations.len(), 1);
+        assert_eq!(eliminations[0].cause, EliminationCause::HeadToHeadCollision);
+
+        // Equal length snakes both lose
+        let config = board(vec!(
+            snake("a", &[(0, 2), (0, 3), (0, 4)]),
+            snake("b", &[(2, 2), (2, 3), (2, 4)]),
+        ), &[]);
+
+        let (next, eliminations) = no_food().next_turn(
+            &config,
+            &moves(&[("a", Move::Right), ("b", Move::Left)]),
+            &mut rng()
+        );
+
+        assert!(next.board.snakes.is_empty());
+        assert_eq!(eliminations.len(), 2);
+    }
+
+    #[test]
+    fn spawns_minimum_food_on_free_squares() {
+        let rules = Rules {
+            food_spawn_chance: 0,
+            minimum_food: 3,
+            ..Default::default()
+        };
+
+        let config = board(vec!(snake("a", &[(1, 1), (1, 2), (1, 3)])), &[]);
+        let (next, _) = rules.next_turn(&config, &moves(&[("a", Move::Up)]), &mut rng());
+
+        let snake = &next.board.snakes[0];
+        assert_eq!(next.board.food.len(), 3);
+        for food in next.board.food.iter() {
+            assert!(!snake.body.contains(food));
+        }
+    }
+
+    fn mode(name: &str) -> Rules {
+        Rules {
+            food_spawn_chance: 0,
+            minimum_food: 0,
+            ..Rules::from_ruleset(&Ruleset { name: String::from(name), ..Default::default() })
+        }
+    }
+
+    #[test]
+    fn reads_mode_from_ruleset() {
+        for name in ["standard", "solo", "royale", "constrictor", "wrapped", "squad"].iter() {
+            let rules = Rules::from_ruleset(&Ruleset { name: String::from(*name), ..Default::default() });
+            assert_eq!(rules.mode.name(), *name);
+            assert_eq!(rules.ruleset().name, *name);
+        }
+
+        assert_eq!(Mode::from_name("some-new-mode"), Mode::Standard);
+
+        // Constrictor games have no food, whatever the settings say
+        let rules = Rules::from_ruleset(&Ruleset { name: String::from("constrictor"), ..Default::default() });
+        assert_eq!((rules.food_spawn_chance, rules.minimum_food), (0, 0));
+    }
+
+    #[test]
+    fn solo_games_go_on_until_the_snake_is_eliminated() {
+        let config = board(vec!(snake("a", &[(1, 1), (1, 2), (1, 3)])), &[]);
+        assert!(mode("standard").is_over(&config));
+        assert!(!mode("solo").is_over(&config));
+
+        let (next, _) = mode("solo").next_turn(&config, &moves(&[("a", Move::Up)]), &mut rng());
+        assert!(!mode("solo").is_over(&next));
+
+        let (next, _) = mode("solo").next_turn(&next, &moves(&[("a", Move::Up)]), &mut rng());
+        assert!(next.board.snakes.is_empty());
+        assert!(mode("solo").is_over(&next));
+    }
+
+    #[test]
+    fn constrictor_snakes_grow_every_turn() {
+        let rules = mode("constrictor");
+
+        let board = rules.create_board(11, 11, &[(String::from("a"), String::from("Snek"))], &mut rng());
+        assert!(board.food.is_empty());
+
+        let mut config = board_with(vec!(snake("a", &[(1, 1), (1, 2), (1, 3)])));
+        config.board.snakes[0].health = 50;
+
+        let (next, _) = rules.next_turn(&config, &moves(&[("a", Move::Right)]), &mut rng());
+        assert_eq!(coords(&next.board.snakes[0]), vec!((2, 1), (1, 1), (1, 2), (1, 2)));
+        assert_eq!(next.board.snakes[0].health, MAX_HEALTH);
+    }
+
+    #[test]
+    fn wrapped_snakes_come_out_the_other_side() {
+        let config = board_with(vec!(snake("a", &[(0, 0), (1, 0), (2, 0)])));
+
+        let (next, eliminations) = mode("wrapped").next_turn(&config, &moves(&[("a", Move::Left)]), &mut rng());
+        assert!(eliminations.is_empty());
+        assert_eq!(coords(&next.board.snakes[0]), vec!((4, 0), (0, 0), (1, 0)));
+
+        let (next, _) = mode("wrapped").next_turn(&config, &moves(&[("a", Move::Up)]), &mut rng());
+        assert_eq!(next.board.snakes[0].head, Coords { x: 0, y: 4 });
+
+        // Walls are still walls in standard games
+        let (_, eliminations) = mode("standard").next_turn(&config, &moves(&[("a", Move::Left)]), &mut rng());
+        assert_eq!(eliminations[0].cause, EliminationCause::OutOfBounds);
+    }
+
+    #[test]
+    fn royale_hazards_hurt_and_close_in() {
+        let mut config = board_with(vec!(snake("a", &[(1, 1), (1, 2), (1, 3)])));
+        config.board.hazards = vec!(Coords { x: 1, y: 0 });
+
+        let rules = Rules { shrink_every: 2, ..mode("royale") };
+
+        let (next, _) = rules.next_turn(&config, &moves(&[("a", Move::Up)]), &mut rng());
+        assert_eq!(next.board.snakes[0].health, 100 - 1 - 14);
+        assert_eq!(next.board.hazards.len(), 1);
+
+        // Every other turn, the hazards take another row or column of the board
+        let (next, _) = rules.next_turn(&next, &moves(&[("a", Move::Right)]), &mut rng());
+        assert_eq!(next.board.snakes[0].health, 100 - 1 - 14 - 1);
+        assert!(next.board.hazards.len() == 5 || next.board.hazards.len() == 6);
+
+        let safe: Vec<Coords> = (0..5)
+            .flat_map(|x| (0..5).map(move |y| Coords { x, y }))
+            .filter(|coords| !next.board.hazards.contains(coords))
+            .collect();
+        assert_eq!(safe.len(), 25 - next.board.hazards.len());
+    }
+
+    #[test]
+    fn squadmates_move_through_each_other_and_share() {
+        let mut config = board_with(vec!(
+            snake("a", &[(1, 1), (1, 2), (1, 3)]),
+            snake("b", &[(2, 2), (2, 3), (3, 3), (4, 3)]),
+            snake("c", &[(4, 0)]),
+        ));
+        config.board.snakes[0].squad = String::from("red");
+        config.board.snakes[1].squad = String::from("red");
+        config.board.snakes[0].health = 20;
+
+        let rules = mode("squad");
+        let (next, eliminations) = rules.next_turn(
+            &config,
+            &moves(&[("a", Move::Right), ("b", Move::Left), ("c", Move::Down)]),
+            &mut rng()
+        );
+
+        // "b" moves into "a"'s neck, and they take on the best of each other's health and length
+        assert!(eliminations.is_empty());
+        assert_eq!(next.board.snakes[0].health, 99);
+        assert_eq!(next.board.snakes[0].body.len(), 4);
+        assert_eq!(next.board.snakes[1].health, 99);
+        assert!(!rules.is_over(&next));
+    }
+
+    #[test]
+    fn squadmates_go_down_together() {
+        let mut config = board_with(vec!(
+            snake("a", &[(0, 1), (0, 2), (0, 3)]),
+            snake("b", &[(2, 2), (2, 3), (2, 4)]),
+            snake("c", &[(4, 2), (4, 3), (4, 4)]),
+        ));
+        config.board.snakes[0].squad = String::from("red");
+        config.board.snakes[1].squad = String::from("red");
+
+        let rules = mode("squad");
+        let (next, eliminations) = rules.next_turn(
+            &config,
+            &moves(&[("a", Move::Left), ("b", Move::Up), ("c", Move::Up)]),
+            &mut rng()
+        );
+
+        assert_eq!(eliminations.len(), 2);
+        assert_eq!(eliminations[0].cause, EliminationCause::OutOfBounds);
+        assert_eq!(eliminations[1].id, "b");
+        assert_eq!(eliminations[1].cause, EliminationCause::SquadEliminated);
+        assert_eq!(eliminations[1].by.as_deref(), Some("a"));
+        assert!(rules.is_over(&next));
+    }
+
+}
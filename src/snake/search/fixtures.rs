@@ -0,0 +1,30 @@
+//
+// Boards shared by the tests of both kinds of search.
+//
+
+use crate::snake::api::{Board, Coords, Snake, SnakeConfig};
+
+pub fn snake(id: &str, body: &[(u32, u32)]) -> Snake {
+    Snake {
+        id: String::from(id),
+        body: body.iter().map(|&(x, y)| Coords { x, y }).collect(),
+        ..Default::default()
+    }
+}
+
+/// 5x5 board with us and the given enemies on it.
+pub fn board(you: Snake, enemies: Vec<Snake>) -> SnakeConfig {
+    let mut snakes = vec!(you.clone());
+    snakes.extend(enemies);
+
+    SnakeConfig {
+        board: Board {
+            width: 5,
+            height: 5,
+            snakes,
+            ..Default::default()
+        },
+        you,
+        ..Default::default()
+    }
+}
@@ -0,0 +1,370 @@
+//
+// Monte Carlo tree search.
+//
+// Snakes move at the same time, so the search is decoupled: every snake keeps its own
+// statistics for each of its moves at each node, and picks its move with UCB1 without
+// knowing what the others picked. The joint move then decides which child to go to.
+//
+
+use std::collections::HashMap;
+use std::thread;
+use std::time::Instant;
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+
+use crate::snake::api::{Snake, SnakeConfig};
+use crate::snake::rules::Rules;
+use crate::snake::utils::Move;
+use crate::snake::topology::Topology;
+use super::{candidate_moves, contested, target};
+
+/// How moves are picked when playing games out from a new node.
+#[derive(PartialEq, Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rollout {
+    /// Any move that stays on the board and doesn't turn back on itself.
+    Random,
+    /// Moves that don't run straight into a snake, where there are any.
+    Heuristic,
+}
+
+/// Number of turns games are played out for from a new node.
+const ROLLOUT_TURNS: u32 = 20;
+
+/// Statistics one snake keeps about one of its moves at a node.
+#[derive(Clone)]
+struct Arm {
+    move_val: Move,
+    visits: u32,
+
+    /// Sum of the snake's rewards from every visit.
+    reward: f64,
+}
+
+#[derive(Clone)]
+struct Node {
+    state: SnakeConfig,
+
+    /// IDs of the snakes on the board, in board order.
+    snakes: Vec<String>,
+
+    /// Moves each snake could make, in board order.
+    arms: Vec<Vec<Arm>>,
+
+    visits: u32,
+
+    /// Child nodes, keyed by the index of the arm each snake picked.
+    children: HashMap<Vec<usize>, usize>,
+}
+
+impl Node {
+
+    fn new(state: SnakeConfig) -> Node {
+        let snakes = state.board.snakes.iter().map(|snake| snake.id.clone()).collect();
+        let arms = state.board.snakes.iter()
+            .map(|snake| candidate_moves(snake, &state).into_iter()
+                .map(|move_val| Arm { move_val, visits: 0, reward: 0.0 })
+                .collect())
+            .collect();
+
+        Node { state, snakes, arms, visits: 0, children: HashMap::new() }
+    }
+
+}
+
+/// Decoupled UCT search over simultaneous moves.
+#[derive(Clone)]
+pub struct Tree {
+    /// Simulates turns under the rules of the game being played.
+    rules: Rules,
+
+    /// ID of our snake.
+    you: String,
+
+    /// Weight of exploring little-visited moves against exploiting ones that have done
+    /// well so far.
+    exploration: f64,
+
+    rollout: Rollout,
+
+    /// Whether there was anyone to beat at the root.
+    contested: bool,
+
+    /// Nodes of the tree, with the root first.
+    nodes: Vec<Node>,
+}
+
+impl Tree {
+
+    pub fn new(config: &SnakeConfig, exploration: f64, rollout: Rollout) -> Tree {
+        Tree {
+            rules: Rules::for_search(&config.game.ruleset),
+            you: config.you.id.clone(),
+            exploration,
+            rollout,
+            contested: contested(config),
+            nodes: vec!(Node::new(config.clone())),
+        }
+    }
+
+    /// Runs up to `iterations` iterations of the search, stopping early once the deadline
+    /// (if any) has passed. Returns the number of iterations run.
+    pub fn run<R: Rng>(&mut self, iterations: u32, deadline: Option<Instant>, rng: &mut R) -> u32 {
+        for iteration in 0..iterations {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return iteration;
+            }
+
+            self.iterate(rng);
+        }
+
+        iterations
+    }
+
+    /// Runs up to `iterations` iterations of the search split between `threads` threads,
+    /// each growing a tree of its own, and stopping early once the deadline (if any) has
+    /// passed. Returns the number of iterations run.
+    ///
+    /// Only the statistics at the root are combined afterwards, so the rest of the tree
+    /// only has what this thread found.
+    pub fn run_parallel<R: Rng>(&mut self, iterations: u32, deadline: Option<Instant>, threads: usize,
+        rng: &mut R) -> u32 {
+
+        let threads = threads.max(1);
+        if threads == 1 {
+            return self.run(iterations, deadline, rng);
+        }
+
+        // This thread picks up whatever doesn't split evenly
+        let share = iterations / threads as u32;
+        let ours = iterations - share * (threads as u32 - 1);
+        let mut helpers: Vec<(Tree, StdRng)> = (1..threads)
+            .map(|_| (self.clone(), StdRng::from_rng(&mut *rng).unwrap()))
+            .collect();
+
+        let total = thread::scope(|scope| {
+            let handles: Vec<_> = helpers.iter_mut()
+                .map(|(tree, rng)| scope.spawn(move || tree.run(share, deadline, rng)))
+                .collect();
+
+            let ours = self.run(ours, deadline, rng);
+            handles.into_iter().map(|handle| handle.join().unwrap()).sum::<u32>() + ours
+        });
+
+        for (helper, _) in helpers {
+            let (root, other) = (&mut self.nodes[0], &helper.nodes[0]);
+            root.visits += other.visits;
+
+            for (arms, other_arms) in root.arms.iter_mut().zip(other.arms.iter()) {
+                for (arm, other_arm) in arms.iter_mut().zip(other_arms.iter()) {
+                    arm.visits += other_arm.visits;
+                    arm.reward += other_arm.reward;
+                }
+            }
+        }
+
+        total
+    }
+
+    /// Our most visited move at the root, or None if we're not on the board.
+    pub fn best_move(&self) -> Option<Move> {
+        let root = &self.nodes[0];
+        let you = root.snakes.iter().position(|id| *id == self.you)?;
+
+        root.arms[you].iter()
+            .max_by_key(|arm| arm.visits)
+            .map(|arm| arm.move_val)
+    }
+
+    /// Walks down the tree to a new node, plays the game out from there, and updates
+    /// the statistics of every move along the way with the result.
+    fn iterate<R: Rng>(&mut self, rng: &mut R) {
+        let mut path: Vec<(usize, Vec<usize>)> = Vec::new();
+        let mut node = 0;
+
+        let rewards = loop {
+            if self.is_over(&self.nodes[node].state) {
+                break self.rewards(&self.nodes[node].state);
+            }
+
+            let picks = self.select(node);
+            path.push((node, picks.clone()));
+
+            if let Some(&child) = self.nodes[node].children.get(&picks) {
+                node = child;
+                continue;
+            }
+
+            // Expand the tree with the new state, and play on from there
+            let state = &self.nodes[node].state;
+            let moves: HashMap<String, Move> = state.board.snakes.iter()
+                .zip(picks.iter())
+                .enumerate()
+                .map(|(idx, (snake, pick))| (snake.id.clone(), self.nodes[node].arms[idx][*pick].move_val))
+                .collect();
+
+            let (next, _) = self.rules.next_turn(state, &moves, rng);
+            let rewards = self.play_out(&next, rng);
+
+            self.nodes.push(Node::new(next));
+            let child = self.nodes.len() - 1;
+            self.nodes[node].children.insert(picks, child);
+
+            break rewards;
+        };
+
+        for (node, picks) in path {
+            let node = &mut self.nodes[node];
+            node.visits += 1;
+
+            for (idx, pick) in picks.into_iter().enumerate() {
+                let arm = &mut node.arms[idx][pick];
+                arm.visits += 1;
+                arm.reward += rewards.get(&node.snakes[idx]).copied().unwrap_or(0.0);
+            }
+        }
+    }
+
+    /// Picks a move for every snake at a node, each going by its own statistics. Moves
+    /// that haven't been tried yet go first.
+    fn select(&self, node: usize) -> Vec<usize> {
+        let node = &self.nodes[node];
+        let log_visits = (node.visits.max(1) as f64).ln();
+
+        node.arms.iter()
+            .map(|arms| {
+                let ucb = |arm: &Arm| {
+                    if arm.visits == 0 {
+                        return f64::INFINITY;
+                    }
+
+                    let visits = arm.visits as f64;
+                    arm.reward / visits + self.exploration * (log_visits / visits).sqrt()
+                };
+
+                (0..arms.len())
+                    .max_by(|a, b| ucb(&arms[*a]).total_cmp(&ucb(&arms[*b])).then(b.cmp(a)))
+                    .unwrap_or(0)
+            })
+            .collect()
+    }
+
+    /// Plays a game out for a while from the given state, and returns each snake's
+    /// reward at the end.
+    fn play_out<R: Rng>(&self, config: &SnakeConfig, rng: &mut R) -> HashMap<String, f64> {
+        let mut state = config.clone();
+
+        for _ in 0..ROLLOUT_TURNS {
+            if self.is_over(&state) {
+                break;
+            }
+
+            let moves: HashMap<String, Move> = state.board.snakes.iter()
+                .map(|snake| (snake.id.clone(), self.rollout_move(snake, &state, rng)))
+                .collect();
+
+            state = self.rules.next_turn(&state, &moves, rng).0;
+        }
+
+        self.rewards(&state)
+    }
+
+    fn rollout_move<R: Rng>(&self, snake: &Snake, config: &SnakeConfig, rng: &mut R) -> Move {
+        let mut moves = candidate_moves(snake, config);
+
+        if self.rollout == Rollout::Heuristic {
+            let topology = Topology::of(config);
+
+            // Tails move out of the way, so they don't count
+            let safe: Vec<Move> = moves.iter()
+                .copied()
+                .filter(|move_val| match target(&topology, snake, *move_val) {
+                    Some(target) => !config.board.snakes.iter()
+                        .any(|other| other.body[..other.body.len() - 1].contains(&target)),
+                    None => false,
+                })
+                .collect();
+
+            if !safe.is_empty() {
+                moves = safe;
+            }
+        }
+
+        *moves.choose(rng).unwrap()
+    }
+
+    /// Whether the game is over, as far as we're concerned.
+    fn is_over(&self, config: &SnakeConfig) -> bool {
+        let snakes = &config.board.snakes;
+        !snakes.iter().any(|snake| snake.id == self.you) || (self.contested && snakes.len() < 2)
+    }
+
+    /// Rewards of the snakes still on the board: one for the last snake standing, and a
+    /// half for everyone if the game is still going. Eliminated snakes get nothing.
+    fn rewards(&self, config: &SnakeConfig) -> HashMap<String, f64> {
+        let snakes = &config.board.snakes;
+        let reward = if snakes.len() == 1 { 1.0 } else { 0.5 };
+
+        snakes.iter().map(|snake| (snake.id.clone(), reward)).collect()
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use super::super::fixtures::{board, snake};
+    use crate::snake::api::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    /// Us about to starve, with food to our right.
+    ///
+    ///     - - - - -
+    ///     - - - - -
+    ///     - - Y F -
+    ///     - - S - -
+    ///     - - S - -
+    fn starving_config() -> SnakeConfig {
+        let mut you = snake("you", &[(2, 2), (2, 3), (2, 4)]);
+        you.health = 1;
+
+        let mut config = board(you.clone(), vec!(snake("enemy", &[(0, 0), (1, 0), (2, 0)])));
+        config.board.food = vec!(Coords { x: 3, y: 2 });
+        config
+    }
+
+    #[test]
+    fn eats_before_starving() {
+        let config = starving_config();
+
+        for rollout in [Rollout::Random, Rollout::Heuristic].iter() {
+            let mut tree = Tree::new(&config, 1.4, *rollout);
+            assert_eq!(tree.run(500, None, &mut StdRng::seed_from_u64(0)), 500);
+            assert_eq!(tree.best_move(), Some(Move::Right));
+        }
+    }
+
+    #[test]
+    fn stops_at_the_deadline() {
+        let config = board(snake("you", &[(2, 2), (2, 3), (2, 4)]), vec!());
+
+        let mut tree = Tree::new(&config, 1.4, Rollout::Random);
+        assert_eq!(tree.run(1000, Some(Instant::now()), &mut StdRng::seed_from_u64(0)), 0);
+    }
+
+    #[test]
+    fn threads_combine_root_statistics() {
+        let config = starving_config();
+
+        let mut tree = Tree::new(&config, 1.4, Rollout::Heuristic);
+        assert_eq!(tree.run_parallel(2000, None, 4, &mut StdRng::seed_from_u64(0)), 2000);
+        assert_eq!(tree.nodes[0].visits, 2000);
+        assert_eq!(tree.best_move(), Some(Move::Right));
+    }
+
+}
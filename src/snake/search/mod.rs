@@ -0,0 +1,579 @@
+//
+// Game tree search. Plays moves out with the local rules engine to look further ahead than
+// the next move.
+//
+// Snakes move at the same time, which minimax can't model directly. The search is
+// paranoid instead: we pick our move first, and then the opponents we're searching
+// against pick theirs knowing what we did, always going for whatever is worst for us.
+//
+
+#[cfg(test)]
+mod fixtures;
+mod mcts;
+mod table;
+mod zobrist;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::collections::HashMap;
+use std::time::Instant;
+
+use rand::rngs::mock::StepRng;
+
+use crate::snake::api::{Coords, Snake, SnakeConfig};
+use crate::snake::evaluator::Evaluator;
+use crate::snake::map::Map;
+use crate::snake::rules::{self, Rules};
+use crate::snake::topology::Topology;
+use crate::snake::utils::Move;
+
+pub use mcts::{Rollout, Tree};
+
+use table::{Bound, Entry, SharedTable};
+use zobrist::Zobrist;
+
+/// Score of a state where we've been eliminated. Always worse than any state we're alive
+/// in.
+pub const LOSS: f64 = -1_000_000.0;
+
+/// Score of a state where we're the last snake standing. Always better than any state
+/// the game carries on from.
+pub const WIN: f64 = 1_000_000.0;
+
+const MOVES: [Move; 4] = [Move::Up, Move::Down, Move::Left, Move::Right];
+
+/// Move picked by a search.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Decision {
+    pub move_val: Move,
+
+    /// Worst-case score of the move.
+    pub score: f64,
+
+    /// Number of turns the search looked ahead.
+    pub depth: u32,
+}
+
+/// Depth-limited paranoid minimax search with alpha-beta pruning.
+pub struct Search {
+    /// Simulates turns under the rules of the game being played.
+    rules: Rules,
+
+    /// ID of our snake.
+    you: String,
+
+    /// IDs of the opponents searched against. Any other snakes just keep going the way
+    /// they're going.
+    opponents: Vec<String>,
+
+    /// Whether there was anyone to beat at the start of the search.
+    contested: bool,
+
+    /// Time the search has to give up by, if any.
+    deadline: Option<Instant>,
+
+    /// Number of threads searching at once.
+    threads: usize,
+
+    /// Set once the deadline has passed, or the search has been called off. Scores from a
+    /// search that was stopped early are meaningless.
+    stopped: AtomicBool,
+
+    /// Scores states at the end of the search that the game carries on from.
+    evaluator: Evaluator,
+
+    zobrist: Zobrist,
+
+    /// Results for positions already searched. Kept between iterative deepening passes,
+    /// so each pass can start with the moves that did best in the last one, and shared
+    /// between threads, so each can pick up where the others left off.
+    table: SharedTable,
+}
+
+impl Search {
+
+    /// Sets up a search from the given state, against up to `opponents` of the snakes
+    /// whose heads are closest to ours. The transposition table has room for results for
+    /// `table_size` positions, or is left out altogether if that's 0.
+    pub fn new(config: &SnakeConfig, opponents: usize, table_size: usize) -> Search {
+        let head = config.you.body[0];
+        let topology = Topology::of(config);
+        let mut enemies: Vec<&Snake> = config.board.snakes.iter()
+            .filter(|snake| snake.id != config.you.id)
+            .collect();
+
+        enemies.sort_by_key(|snake| {
+            let enemy_head = snake.body[0];
+            topology.distance((head.x, head.y), (enemy_head.x, enemy_head.y))
+        });
+
+        Search {
+            rules: Rules::for_search(&config.game.ruleset),
+            you: config.you.id.clone(),
+            opponents: enemies.iter().take(opponents).map(|snake| snake.id.clone()).collect(),
+            contested: contested(config),
+            deadline: None,
+            threads: 1,
+            stopped: AtomicBool::new(false),
+            evaluator: Evaluator::default(),
+            zobrist: Zobrist::new(config),
+            table: SharedTable::new(table_size),
+        }
+    }
+
+    /// Makes the search give up once the deadline has passed.
+    pub fn with_deadline(mut self, deadline: Instant) -> Search {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Makes the search score states with the given evaluator.
+    pub fn with_evaluator(mut self, evaluator: Evaluator) -> Search {
+        self.evaluator = evaluator;
+        self
+    }
+
+    /// Makes iterative deepening search on `threads` threads at once (at least one).
+    pub fn with_threads(mut self, threads: usize) -> Search {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Searches `depth` turns ahead, and returns the move with the best worst-case
+    /// outcome. Returns None if we're not on the board, or the search ran out of time.
+    pub fn best_move(&self, config: &SnakeConfig, depth: u32) -> Option<Decision> {
+        let you = self.find_you(config)?;
+        let depth = depth.max(1);
+        let key = self.zobrist.hash(config);
+
+        let mut alpha = f64::NEG_INFINITY;
+        let mut best: Option<Decision> = None;
+
+        let previous_best = self.table.get(key).and_then(|entry| entry.best_move);
+        for our_move in ordered_moves(you, config, previous_best) {
+            let score = self.min_value(config, our_move, depth, 0, alpha, f64::INFINITY);
+
+            if best.map_or(true, |best| score > best.score) {
+                best = Some(Decision { move_val: our_move, score, depth });
+            }
+            alpha = alpha.max(score);
+        }
+
+        if self.stopped.load(Ordering::Relaxed) {
+            return None;
+        }
+
+        if let Some(decision) = best {
+            self.table.store(Entry {
+                key,
+                depth,
+                value: decision.score,
+                bound: Bound::Exact,
+                best_move: Some(decision.move_val),
+            });
+        }
+
+        best
+    }
+
+    /// Searches one turn ahead, then two, and so on until `max_depth` or the deadline,
+    /// whichever comes first. Returns the move picked by the deepest search that finished,
+    /// or None if not even the first one did.
+    ///
+    /// With more than one thread, every thread deepens its own search, sharing results
+    /// through the transposition table. Half of the helper threads start a turn further
+    /// ahead, so they fill the table in for the passes to come. The search is called off
+    /// once this thread is done.
+    pub fn iterative_deepening(&self, config: &SnakeConfig, max_depth: u32) -> Option<Decision> {
+
+        // Calling off the last search doesn't call this one off, though the deadline
+        // might have passed since
+        self.stopped.store(false, Ordering::Relaxed);
+        if self.threads == 1 {
+            return self.deepen(config, 1, max_depth);
+        }
+
+        thread::scope(|scope| {
+            let helpers: Vec<_> = (1..self.threads)
+                .map(|idx| scope.spawn(move || self.deepen(config, 1 + idx as u32 % 2, max_depth)))
+                .collect();
+
+            let main = self.deepen(config, 1, max_depth);
+            self.stopped.store(true, Ordering::Relaxed);
+
+            helpers.into_iter()
+                .filter_map(|helper| helper.join().unwrap())
+                .fold(main, |best, decision| match best {
+                    Some(best) if best.depth >= decision.depth => Some(best),
+                    _ => Some(decision),
+                })
+        })
+    }
+
+    fn deepen(&self, config: &SnakeConfig, first_depth: u32, max_depth: u32) -> Option<Decision> {
+        let mut best = None;
+
+        for depth in first_depth..=max_depth.max(first_depth) {
+            let decision = match self.best_move(config, depth) {
+                Some(decision) => decision,
+                None => break,
+            };
+            best = Some(decision);
+
+            // Once the outcome is settled either way, looking further ahead won't change it
+            if decision.score >= WIN / 2.0 || decision.score <= LOSS / 2.0 {
+                break;
+            }
+        }
+
+        best
+    }
+
+    /// Value of a state where it's our turn to pick a move.
+    fn max_value(&self, config: &SnakeConfig, depth: u32, ply: u32, mut alpha: f64, beta: f64) -> f64 {
+        if self.out_of_time() {
+            return 0.0;
+        }
+
+        let you = match self.find_you(config) {
+            Some(you) => you,
+            // Dying later is better than dying sooner
+            None => return LOSS + ply as f64,
+        };
+
+        if self.contested && config.board.snakes.len() == 1 {
+            // Winning sooner is better than winning later
+            return WIN - ply as f64;
+        }
+
+        if depth == 0 {
+            return self.evaluator.evaluate(config, &Map::new(config));
+        }
+
+        let key = self.zobrist.hash(config);
+        let stored = self.table.get(key);
+
+        if let Some(entry) = stored.filter(|entry| entry.depth >= depth) {
+            let value = from_table(entry.value, ply);
+            match entry.bound {
+                Bound::Exact => return value,
+                Bound::Lower if value >= beta => return value,
+                Bound::Upper if value <= alpha => return value,
+                _ => (),
+            }
+        }
+
+        let original_alpha = alpha;
+        let mut best = f64::NEG_INFINITY;
+        let mut best_move = None;
+
+        for our_move in ordered_moves(you, config, stored.and_then(|entry| entry.best_move)) {
+            let score = self.min_value(config, our_move, depth, ply, alpha, beta);
+
+            if score > best {
+                best = score;
+                best_move = Some(our_move);
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        if !self.stopped.load(Ordering::Relaxed) {
+            let bound = if best <= original_alpha {
+                Bound::Upper
+            }
+            else if best >= beta {
+                Bound::Lower
+            }
+            else {
+                Bound::Exact
+            };
+
+            self.table.store(Entry { key, depth, value: to_table(best, ply), bound, best_move });
+        }
+
+        best
+    }
+
+    /// Value of us making `our_move`, with our opponents then making whichever moves are
+    /// worst for us.
+    fn min_value(&self, config: &SnakeConfig, our_move: Move, depth: u32, ply: u32, alpha: f64, mut beta: f64)
+        -> f64 {
+
+        // Food doesn't spawn, so the rules never actually use this
+        let mut rng = StepRng::new(0, 0);
+
+        let mut worst = f64::INFINITY;
+        for mut moves in self.opponent_moves(config) {
+            moves.insert(self.you.clone(), our_move);
+
+            let (next, _) = self.rules.next_turn(config, &moves, &mut rng);
+            let score = self.max_value(&next, depth - 1, ply + 1, alpha, beta);
+
+            worst = worst.min(score);
+            beta = beta.min(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        worst
+    }
+
+    /// Every combination of moves the opponents still on the board could make.
+    fn opponent_moves(&self, config: &SnakeConfig) -> Vec<HashMap<String, Move>> {
+        let mut combinations = vec!(HashMap::new());
+
+        for snake in config.board.snakes.iter().filter(|snake| self.opponents.contains(&snake.id)) {
+            let moves = candidate_moves(snake, config);
+
+            combinations = combinations.iter()
+                .flat_map(|combination| moves.iter().map(move |move_val| {
+                    let mut combination = combination.clone();
+                    combination.insert(snake.id.clone(), *move_val);
+                    combination
+                }))
+                .collect();
+        }
+
+        combinations
+    }
+
+    fn out_of_time(&self) -> bool {
+        if !self.stopped.load(Ordering::Relaxed) && self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            self.stopped.store(true, Ordering::Relaxed);
+        }
+
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    fn find_you<'a>(&self, config: &'a SnakeConfig) -> Option<&'a Snake> {
+        config.board.snakes.iter().find(|snake| snake.id == self.you)
+    }
+
+}
+
+/// Whether there's anyone for us to beat. Being the last snake standing only counts as a
+/// win if there is.
+fn contested(config: &SnakeConfig) -> bool {
+    config.board.snakes.iter().any(|snake| snake.id != config.you.id)
+}
+
+/// Our candidate moves, with the best move found by an earlier search (if any) first.
+/// Trying the best move first lets alpha-beta pruning rule more of the others out.
+fn ordered_moves(you: &Snake, config: &SnakeConfig, best: Option<Move>) -> Vec<Move> {
+    let mut moves = candidate_moves(you, config);
+
+    if let Some(idx) = moves.iter().position(|move_val| Some(*move_val) == best) {
+        moves[..=idx].rotate_right(1);
+    }
+
+    moves
+}
+
+/// Wins and losses are scored by how many turns away they are from the root of the
+/// search. Stored scores count from the position they're stored for instead, so they're
+/// still right when the position comes up at a different point in the search.
+fn to_table(score: f64, ply: u32) -> f64 {
+    if score >= WIN / 2.0 {
+        score + ply as f64
+    }
+    else if score <= LOSS / 2.0 {
+        score - ply as f64
+    }
+    else {
+        score
+    }
+}
+
+/// Turns a stored score back into one that counts from the root of the search.
+fn from_table(value: f64, ply: u32) -> f64 {
+    if value >= WIN / 2.0 {
+        value - ply as f64
+    }
+    else if value <= LOSS / 2.0 {
+        value + ply as f64
+    }
+    else {
+        value
+    }
+}
+
+/// Moves worth considering for a snake: anything that stays on the board and doesn't turn
+/// back on itself. Snakes with nowhere to go make their default move.
+fn candidate_moves(snake: &Snake, config: &SnakeConfig) -> Vec<Move> {
+    let topology = Topology::of(config);
+    let head = snake.body[0];
+    let neck = snake.body.iter().find(|coords| **coords != head);
+
+    let moves: Vec<Move> = MOVES.iter()
+        .copied()
+        .filter(|move_val| {
+            match target(&topology, snake, *move_val) {
+                Some(target) => Some(&target) != neck,
+                None => false,
+            }
+        })
+        .collect();
+
+    if moves.is_empty() {
+        vec!(rules::default_move(snake))
+    }
+    else {
+        moves
+    }
+}
+
+/// Cell a snake's head moves into with a move, or None if that's off the board.
+fn target(topology: &Topology, snake: &Snake, move_val: Move) -> Option<Coords> {
+    let head = snake.body[0];
+    topology.step((head.x, head.y), move_val).map(|(x, y)| Coords { x, y })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use super::fixtures::{board, snake};
+    use std::time::Duration;
+
+    #[test]
+    fn stays_clear_of_longer_snakes() {
+        // Board state:
+        //   - - - - -
+        //   - - - E E
+        //   - - Y - E
+        //   - - S - E
+        //   - - S - -
+        //
+        // The enemy is longer than us, and could meet us head on if we went up or right.
+        // Going left is the only move it can't punish.
+        let config = board(
+            snake("you", &[(2, 2), (2, 3), (2, 4)]),
+            vec!(snake("enemy", &[(3, 1), (4, 1), (4, 2), (4, 3)])),
+        );
+
+        let search = Search::new(&config, 1, 1024);
+        let decision = search.best_move(&config, 2).unwrap();
+
+        assert_eq!(decision.move_val, Move::Left);
+        assert!(decision.score > LOSS / 2.0);
+    }
+
+    #[test]
+    fn goes_for_the_kill() {
+        // Board state:
+        //   E E - - -
+        //   - Y - - -
+        //   - S - - -
+        //   - S - - -
+        //   - S - - -
+        //
+        // The enemy is cornered, and its only way out is where we can meet it head on.
+        let config = board(
+            snake("you", &[(1, 1), (1, 2), (1, 3), (1, 4)]),
+            vec!(snake("enemy", &[(0, 0), (1, 0)])),
+        );
+
+        let search = Search::new(&config, 1, 1024);
+        let decision = search.best_move(&config, 2).unwrap();
+
+        assert_eq!(decision.move_val, Move::Left);
+        assert_eq!(decision.score, WIN - 1.0);
+
+        // No point looking any further ahead once the win is in the bag
+        let decision = search.iterative_deepening(&config, 5).unwrap();
+        assert_eq!(decision.move_val, Move::Left);
+        assert_eq!(decision.depth, 1);
+    }
+
+    #[test]
+    fn only_searches_nearest_opponents() {
+        let config = board(
+            snake("you", &[(2, 2), (2, 3), (2, 4)]),
+            vec!(
+                snake("far", &[(4, 4), (4, 3)]),
+                snake("near", &[(2, 0), (1, 0)]),
+            ),
+        );
+
+        let search = Search::new(&config, 1, 1024);
+        assert_eq!(search.opponents, vec!(String::from("near")));
+    }
+
+    #[test]
+    fn deepening_stops_at_the_deadline() {
+        let config = board(
+            snake("you", &[(2, 2), (2, 3), (2, 4)]),
+            vec!(snake("enemy", &[(0, 0), (0, 1), (0, 2)])),
+        );
+
+        // With time to spare, the search goes as deep as it's allowed to
+        let search = Search::new(&config, 1, 1024)
+            .with_deadline(Instant::now() + Duration::from_secs(60));
+        assert_eq!(search.iterative_deepening(&config, 2).unwrap().depth, 2);
+
+        // Out of time before even starting, nothing gets finished
+        let search = Search::new(&config, 1, 1024).with_deadline(Instant::now());
+        assert!(search.iterative_deepening(&config, 2).is_none());
+    }
+
+    #[test]
+    fn transposition_table_keeps_results_the_same() {
+        let config = board(
+            snake("you", &[(2, 2), (2, 3), (2, 4)]),
+            vec!(snake("enemy", &[(3, 1), (4, 1), (4, 2), (4, 3)])),
+        );
+
+        let with_table = Search::new(&config, 1, 1 << 12).iterative_deepening(&config, 4).unwrap();
+        let without = Search::new(&config, 1, 0).iterative_deepening(&config, 4).unwrap();
+
+        assert_eq!(with_table.move_val, without.move_val);
+        assert_eq!(with_table.score, without.score);
+    }
+
+    #[test]
+    fn scores_count_from_where_they_are_stored() {
+        for score in [WIN - 5.0, LOSS + 5.0, 42.0].iter() {
+            assert_eq!(from_table(to_table(*score, 3), 3), *score);
+        }
+
+        // A loss two turns after a position stored at ply 3 is still two turns away when
+        // the position comes up at ply 1
+        assert_eq!(from_table(to_table(LOSS + 5.0, 3), 1), LOSS + 3.0);
+    }
+
+    #[test]
+    fn threads_agree_on_the_move() {
+        let config = board(
+            snake("you", &[(2, 2), (2, 3), (2, 4)]),
+            vec!(snake("enemy", &[(3, 1), (4, 1), (4, 2), (4, 3)])),
+        );
+
+        let decision = Search::new(&config, 1, 1 << 12)
+            .with_threads(4)
+            .iterative_deepening(&config, 4)
+            .unwrap();
+
+        assert_eq!(decision.move_val, Move::Left);
+        assert_eq!(decision.depth, 4);
+    }
+
+    #[test]
+    fn searches_again_after_calling_off_helpers() {
+        let config = board(
+            snake("you", &[(2, 2), (2, 3), (2, 4)]),
+            vec!(snake("enemy", &[(3, 1), (4, 1), (4, 2), (4, 3)])),
+        );
+
+        let search = Search::new(&config, 1, 1 << 12).with_threads(2);
+        let first = search.iterative_deepening(&config, 3).unwrap();
+        let second = search.iterative_deepening(&config, 4).unwrap();
+
+        assert_eq!(first.depth, 3);
+        assert_eq!(second.depth, 4);
+    }
+
+}
@@ -0,0 +1,170 @@
+//
+// Transposition table. Remembers what the search found out about positions it's already
+// seen, so it doesn't have to work it out again.
+//
+
+use std::sync::Mutex;
+
+use crate::snake::utils::Move;
+
+/// Number of separately locked shards in a shared table.
+const SHARDS: usize = 64;
+
+/// How a stored value relates to the position's true value. Searches that get cut short
+/// by alpha-beta pruning only find out a bound on it.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Bound {
+    Exact,
+    /// The true value is at least the stored one.
+    Lower,
+    /// The true value is at most the stored one.
+    Upper,
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Entry {
+    /// Zobrist hash of the position.
+    pub key: u64,
+
+    /// Number of turns the position was searched ahead.
+    pub depth: u32,
+
+    pub value: f64,
+    pub bound: Bound,
+
+    /// Best move found for us, if the search got as far as trying one.
+    pub best_move: Option<Move>,
+}
+
+/// Fixed-size table of search results. Each position has one slot, picked by its hash, so
+/// positions can push each other out.
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+}
+
+impl TranspositionTable {
+
+    /// Creates a table with room for `capacity` entries. A table with no room never
+    /// remembers anything, which leaves the search to work everything out from scratch.
+    pub fn new(capacity: usize) -> TranspositionTable {
+        TranspositionTable {
+            entries: vec![None; capacity],
+        }
+    }
+
+    /// Stored result for a position, if there is one.
+    pub fn get(&self, key: u64) -> Option<&Entry> {
+        self.entries.get(self.slot(key)?)?.as_ref().filter(|entry| entry.key == key)
+    }
+
+    /// Stores a result. Results for other positions are replaced, but a result for the
+    /// same position is only replaced by one that searched at least as deep.
+    pub fn store(&mut self, entry: Entry) {
+        let slot = match self.slot(entry.key) {
+            Some(slot) => slot,
+            None => return,
+        };
+
+        match self.entries[slot] {
+            Some(existing) if existing.key == entry.key && existing.depth > entry.depth => (),
+            _ => self.entries[slot] = Some(entry),
+        }
+    }
+
+    fn slot(&self, key: u64) -> Option<usize> {
+        match self.entries.len() {
+            0 => None,
+            len => Some((key % len as u64) as usize),
+        }
+    }
+
+}
+
+/// Transposition table shared between threads. It's split into shards with a lock each,
+/// so threads only hold each other up when they're after positions in the same shard.
+pub struct SharedTable {
+    shards: Vec<Mutex<TranspositionTable>>,
+}
+
+impl SharedTable {
+
+    /// Creates a table with room for `capacity` entries, spread across the shards.
+    pub fn new(capacity: usize) -> SharedTable {
+        SharedTable {
+            shards: (0..SHARDS)
+                .map(|idx| capacity / SHARDS + (idx < capacity % SHARDS) as usize)
+                .map(|capacity| Mutex::new(TranspositionTable::new(capacity)))
+                .collect(),
+        }
+    }
+
+    /// Stored result for a position, if there is one.
+    pub fn get(&self, key: u64) -> Option<Entry> {
+        self.shard(key).lock().unwrap().get(key).copied()
+    }
+
+    /// Stores a result, the same as for an unshared table.
+    pub fn store(&self, entry: Entry) {
+        self.shard(entry.key).lock().unwrap().store(entry);
+    }
+
+    /// Shard a position's result goes in. Slots within a shard go by the low bits of the
+    /// hash, so shards go by the high ones.
+    fn shard(&self, key: u64) -> &Mutex<TranspositionTable> {
+        &self.shards[((key >> 32) % SHARDS as u64) as usize]
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn entry(key: u64, depth: u32) -> Entry {
+        Entry { key, depth, value: depth as f64, bound: Bound::Exact, best_move: Some(Move::Up) }
+    }
+
+    #[test]
+    fn stores_and_replaces_entries() {
+        let mut table = TranspositionTable::new(4);
+
+        table.store(entry(1, 3));
+        assert_eq!(table.get(1), Some(&entry(1, 3)));
+        assert_eq!(table.get(2), None);
+
+        // Shallower results for the same position don't replace deeper ones
+        table.store(entry(1, 2));
+        assert_eq!(table.get(1).unwrap().depth, 3);
+        table.store(entry(1, 4));
+        assert_eq!(table.get(1).unwrap().depth, 4);
+
+        // Positions sharing a slot push each other out
+        table.store(entry(5, 1));
+        assert_eq!(table.get(5), Some(&entry(5, 1)));
+        assert_eq!(table.get(1), None);
+    }
+
+    #[test]
+    fn shares_entries_across_shards() {
+        let table = SharedTable::new(1 << 12);
+
+        for key in 0..SHARDS as u64 {
+            table.store(entry(key << 32 | key, 2));
+        }
+        for key in 0..SHARDS as u64 {
+            assert_eq!(table.get(key << 32 | key), Some(entry(key << 32 | key, 2)));
+        }
+
+        assert_eq!(SharedTable::new(0).get(0), None);
+    }
+
+    #[test]
+    fn empty_table_remembers_nothing() {
+        let mut table = TranspositionTable::new(0);
+
+        table.store(entry(1, 3));
+        assert_eq!(table.get(1), None);
+    }
+
+}
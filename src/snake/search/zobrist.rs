@@ -0,0 +1,196 @@
+//
+// Zobrist hashing of game states, so positions reached by different move orders can be
+// recognised as the same.
+//
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use crate::snake::api::{Coords, SnakeConfig};
+use crate::snake::rules::MAX_HEALTH;
+
+/// Ways a body segment can lead on to the next one: in one of four directions, or not at
+/// all (the end of the tail, or segments stacked on the same cell).
+const SHAPES: usize = 5;
+
+/// Random keys for everything that tells one state apart from another. A state's hash is
+/// the XOR of the keys of everything in it.
+pub struct Zobrist {
+    width: u32,
+    height: u32,
+
+    /// IDs of the snakes the keys are for. Snakes are told apart by their place in this
+    /// list, so every state hashed must only contain these snakes.
+    snakes: Vec<String>,
+
+    /// Keys for a snake's head being on a cell, by snake and cell.
+    heads: Vec<u64>,
+
+    /// Keys for a snake's body passing through a cell, by snake, cell and where the body
+    /// goes next.
+    segments: Vec<u64>,
+
+    /// Keys for each snake's health, by snake and health.
+    health: Vec<u64>,
+
+    /// Keys for food being on a cell, by cell.
+    food: Vec<u64>,
+}
+
+impl Zobrist {
+
+    /// Generates keys for states that can follow on from the given one.
+    pub fn new(config: &SnakeConfig) -> Zobrist {
+        let board = &config.board;
+        let cells = (board.width * board.height) as usize;
+        let snakes: Vec<String> = board.snakes.iter().map(|snake| snake.id.clone()).collect();
+
+        // Keys only need to be random-looking, not unpredictable, and a fixed seed keeps
+        // searches repeatable
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut keys = |count: usize| -> Vec<u64> { (0..count).map(|_| rng.gen()).collect() };
+
+        Zobrist {
+            width: board.width,
+            height: board.height,
+            heads: keys(snakes.len() * cells),
+            segments: keys(snakes.len() * cells * SHAPES),
+            health: keys(snakes.len() * (MAX_HEALTH as usize + 1)),
+            food: keys(cells),
+            snakes,
+        }
+    }
+
+    pub fn hash(&self, config: &SnakeConfig) -> u64 {
+        let board = &config.board;
+        let cells = (self.width * self.height) as usize;
+        let mut hash = 0;
+
+        for snake in board.snakes.iter() {
+            let idx = match self.snakes.iter().position(|id| *id == snake.id) {
+                Some(idx) => idx,
+                None => continue,
+            };
+
+            if let Some(cell) = self.cell(snake.body[0]) {
+                hash ^= self.heads[idx * cells + cell];
+            }
+
+            for (segment, next) in snake.body.iter().zip(snake.body.iter().skip(1).map(Some).chain(Some(None))) {
+                if let Some(cell) = self.cell(*segment) {
+                    hash ^= self.segments[(idx * cells + cell) * SHAPES + shape(*segment, next)];
+                }
+            }
+
+            let health = std::cmp::min(snake.health, MAX_HEALTH) as usize;
+            hash ^= self.health[idx * (MAX_HEALTH as usize + 1) + health];
+        }
+
+        for food in board.food.iter() {
+            if let Some(cell) = self.cell(*food) {
+                hash ^= self.food[cell];
+            }
+        }
+
+        hash
+    }
+
+    /// Index of a cell, if it's on the board.
+    fn cell(&self, coords: Coords) -> Option<usize> {
+        if coords.x < self.width && coords.y < self.height {
+            Some((coords.y * self.width + coords.x) as usize)
+        }
+        else {
+            None
+        }
+    }
+
+}
+
+/// Where the body goes from one segment to the next.
+fn shape(segment: Coords, next: Option<&Coords>) -> usize {
+    match next {
+        Some(next) if next.x > segment.x => 0,
+        Some(next) if next.x < segment.x => 1,
+        Some(next) if next.y > segment.y => 2,
+        Some(next) if next.y < segment.y => 3,
+        _ => 4,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::snake::api::*;
+
+    fn config() -> SnakeConfig {
+        SnakeConfig {
+            board: Board {
+                width: 5,
+                height: 5,
+                food: vec!(Coords { x: 4, y: 4 }),
+                snakes: vec!(
+                    Snake {
+                        id: String::from("a"),
+                        body: vec!(Coords { x: 1, y: 1 }, Coords { x: 1, y: 2 }, Coords { x: 2, y: 2 }),
+                        ..Default::default()
+                    },
+                    Snake {
+                        id: String::from("b"),
+                        body: vec!(Coords { x: 3, y: 1 }, Coords { x: 3, y: 2 }),
+                        ..Default::default()
+                    },
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn same_states_hash_the_same() {
+        let config = config();
+        let zobrist = Zobrist::new(&config);
+
+        // Neither the turn number nor the order of the snakes matter
+        let mut same = config.clone();
+        same.turn += 10;
+        same.board.snakes.reverse();
+
+        assert_eq!(zobrist.hash(&config), zobrist.hash(&same));
+        assert_eq!(Zobrist::new(&config).hash(&config), zobrist.hash(&config));
+    }
+
+    #[test]
+    fn different_states_hash_differently() {
+        let config = config();
+        let zobrist = Zobrist::new(&config);
+        let hash = zobrist.hash(&config);
+
+        let mut hungrier = config.clone();
+        hungrier.board.snakes[0].health -= 1;
+        assert_ne!(zobrist.hash(&hungrier), hash);
+
+        let mut eaten = config.clone();
+        eaten.board.food.clear();
+        assert_ne!(zobrist.hash(&eaten), hash);
+
+        // Same cells, but the other way round
+        let mut reversed = config.clone();
+        reversed.board.snakes[0].body.reverse();
+        assert_ne!(zobrist.hash(&reversed), hash);
+
+        // Same cells, but different snakes in them
+        let mut swapped = config.clone();
+        swapped.board.snakes[0].id = String::from("b");
+        swapped.board.snakes[1].id = String::from("a");
+        assert_ne!(zobrist.hash(&swapped), hash);
+
+        // Stacked segments after eating
+        let mut grown = config.clone();
+        grown.board.snakes[1].body.push(Coords { x: 3, y: 2 });
+        assert_ne!(zobrist.hash(&grown), hash);
+    }
+
+}
@@ -0,0 +1,173 @@
+use std::time::Instant;
+
+use crate::snake::api::SnakeConfig;
+use crate::snake::map::Map;
+use crate::snake::path::{
+    cautious_cost, cheapest_path_to, distance_field, shortest_path_after, DistanceField, PathNode
+};
+use crate::snake::utils::Move;
+use super::{Strategy, TailChaser};
+
+/// Chases its tail like `TailChaser`, but goes looking for food when it gets hungry.
+///
+/// A forager is hungry when its health drops below a threshold, or when an enemy is at
+/// least as long as it is (and so would win a head-to-head collision). When hungry, it
+/// heads for the nearest food it can get to before any enemy head, as long as it could
+/// still find its tail after eating.
+pub struct Forager {
+    health_threshold: u8,
+}
+
+impl Forager {
+
+    pub fn new(health_threshold: u8) -> Forager {
+        Forager { health_threshold }
+    }
+
+    fn is_hungry(&self, config: &SnakeConfig, map: &Map) -> bool {
+        config.you.health < self.health_threshold || config.board.snakes.iter()
+            .filter(|snake| snake.id != config.you.id)
+            .any(|snake| snake.body.len() >= map.length())
+    }
+
+    /// Path to the food worth going for, if there is any. Paths keep clear of walls and
+    /// enemy heads where they can, as long as taking the long way round doesn't lose us
+    /// the race for the food.
+    fn path_to_food(&self, config: &SnakeConfig, map: &Map) -> Option<Vec<PathNode>> {
+        let ours = distance_field(map, map.head());
+        let theirs: Vec<DistanceField> = config.board.snakes.iter()
+            .filter(|snake| snake.id != config.you.id)
+            .map(|snake| distance_field(map, (snake.body[0].x, snake.body[0].y)))
+            .collect();
+
+        let mut food: Vec<((u32, u32), u32)> = config.board.food.iter()
+            .filter_map(|food| ours.distance_to((food.x, food.y)).map(|dist| ((food.x, food.y), dist)))
+            .collect();
+        food.sort_by_key(|(_, dist)| *dist);
+
+        // Turns until the first enemy could get to a cell
+        let enemy_dist = |coords| theirs.iter()
+            .filter_map(|field| field.distance_to(coords))
+            .min()
+            .unwrap_or(u32::MAX);
+
+        // Food isn't worth racing for if an enemy will get there first (or at the same
+        // time, and take us out with it). Don't eat our way into a dead end either. The
+        // board will have moved on by the time we've eaten, but eating makes us longer,
+        // so our body clears a turn later than it otherwise would.
+        let (target, _) = food.into_iter()
+            .filter(|(coords, dist)| *dist < enemy_dist(*coords))
+            .find(|(coords, dist)| shortest_path_after(map, *coords, map.tail(), dist.saturating_sub(1)).is_some())?;
+
+        match cheapest_path_to(map, map.head(), target, cautious_cost(map)) {
+            Some(path) if ((path.len() - 1) as u32) < enemy_dist(target) => Some(path),
+            _ => ours.path_to(target),
+        }
+    }
+
+}
+
+impl Strategy for Forager {
+
+    fn next_move(&self, config: &SnakeConfig, map: &Map, received: Instant) -> Move {
+        if self.is_hungry(config, map) {
+            let next_move = self.path_to_food(config, map)
+                .and_then(|path| path.first().and_then(|node| node.next_move));
+
+            if let Some(next_move) = next_move {
+                return next_move;
+            }
+        }
+
+        TailChaser.next_move(config, map, received)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::snake::api::*;
+
+    /// Board with us in the middle of the top row, food at either end, and an enemy just
+    /// below the food on the right.
+    ///
+    ///     F - T S Y - - F
+    ///     - - - - - - - E
+    ///     - - - - - - - E
+    fn food_config(health: u8, with_enemy: bool) -> SnakeConfig {
+        let you = Snake {
+            id: String::from("you"),
+            health,
+            body: vec!(
+                Coords { x: 4, y: 0 },
+                Coords { x: 3, y: 0 },
+                Coords { x: 2, y: 0 },
+            ),
+            ..Default::default()
+        };
+
+        let mut snakes = vec!(you.clone());
+        if with_enemy {
+            snakes.push(Snake {
+                id: String::from("enemy"),
+                body: vec!(Coords { x: 7, y: 1 }, Coords { x: 7, y: 2 }),
+                ..Default::default()
+            });
+        }
+
+        SnakeConfig {
+            board: Board {
+                width: 8,
+                height: 3,
+                food: vec!(Coords { x: 0, y: 0 }, Coords { x: 7, y: 0 }),
+                snakes,
+                ..Default::default()
+            },
+            you,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn chases_tail_when_not_hungry() {
+        let config = food_config(100, false);
+        let map = Map::new(&config);
+
+        assert_eq!(Forager::new(40).next_move(&config, &map, Instant::now()), TailChaser.next_move(&config, &map, Instant::now()));
+    }
+
+    #[test]
+    fn goes_for_nearest_food_when_hungry() {
+        let config = food_config(20, false);
+        let map = Map::new(&config);
+
+        assert_eq!(Forager::new(40).next_move(&config, &map, Instant::now()), Move::Right);
+    }
+
+    #[test]
+    fn leaves_food_enemies_get_to_first() {
+        // The enemy is shorter than us, so we're only hungry because of our health. It's
+        // right next to the nearest food though, so we go the long way round to the other
+        let config = food_config(20, true);
+        let map = Map::new(&config);
+
+        assert_eq!(Forager::new(40).next_move(&config, &map, Instant::now()), Move::Down);
+    }
+
+    #[test]
+    fn gets_hungry_when_outgrown() {
+        let mut config = food_config(100, true);
+        config.board.snakes[1].body.push(Coords { x: 6, y: 2 });
+        let map = Map::new(&config);
+
+        let forager = Forager::new(40);
+        assert!(forager.is_hungry(&config, &map));
+
+        let config = food_config(100, false);
+        let map = Map::new(&config);
+        assert!(!forager.is_hungry(&config, &map));
+    }
+
+}
@@ -0,0 +1,38 @@
+use std::time::Instant;
+
+use crate::snake::api::SnakeConfig;
+use crate::snake::evaluator::Evaluator;
+use crate::snake::map::Map;
+use crate::snake::search::Search;
+use crate::snake::utils::Move;
+use super::{search_threads, time_budget, Settings, Strategy};
+
+/// Looks ahead as many turns as it has time for, assuming the nearest opponents will do
+/// whatever is worst for us, and picks the move with the best worst case.
+pub struct Minimax {
+    settings: Settings,
+}
+
+impl Minimax {
+
+    pub fn new(settings: &Settings) -> Minimax {
+        Minimax { settings: settings.clone() }
+    }
+
+}
+
+impl Strategy for Minimax {
+
+    fn next_move(&self, config: &SnakeConfig, map: &Map, received: Instant) -> Move {
+        let deadline = received + time_budget(config, &self.settings);
+
+        Search::new(config, self.settings.search_opponents, self.settings.search_table_size)
+            .with_deadline(deadline)
+            .with_evaluator(Evaluator::new(self.settings.weights.clone()))
+            .with_threads(search_threads(&self.settings))
+            .iterative_deepening(config, self.settings.search_depth)
+            .map(|decision| decision.move_val)
+            .unwrap_or_else(|| map.find_spacious_move())
+    }
+
+}
@@ -0,0 +1,165 @@
+//
+// Snake strategies. A strategy decides how a snake behaves over the course of a game.
+//
+
+mod forager;
+mod minimax;
+mod monte_carlo;
+mod tail_chaser;
+
+pub use forager::Forager;
+pub use minimax::Minimax;
+pub use monte_carlo::MonteCarlo;
+pub use super::search::Rollout;
+pub use tail_chaser::TailChaser;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use super::api::{SnakeConfig, DEFAULT_TIMEOUT};
+use super::evaluator::Weights;
+use super::map::Map;
+use super::utils::Move;
+
+/// Name of the strategy used when none is requested.
+pub const DEFAULT_STRATEGY: &str = "tailchaser";
+
+/// Names of all available strategies.
+pub const STRATEGY_NAMES: &[&str] = &["forager", "mcts", "minimax", "tailchaser"];
+
+/// Knobs for tuning how strategies play. Strategies ignore settings that don't apply to
+/// them.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Health below which snakes go looking for food.
+    pub hunger_threshold: u8,
+
+    /// Most turns searching strategies look ahead. They stop short of this if they run
+    /// out of time.
+    pub search_depth: u32,
+
+    /// Number of opponents searching strategies consider, starting with the closest.
+    pub search_opponents: usize,
+
+    /// Number of positions searching strategies remember results for while picking a
+    /// move. 0 turns remembering results off.
+    pub search_table_size: usize,
+
+    /// Number of threads searching strategies use to pick each move, including the
+    /// server worker thread handling the request, or None for one per CPU core. Every
+    /// game being played at once searches on this many threads, so the server shares the
+    /// cores out between its worker threads when the number of workers is set.
+    pub search_threads: Option<usize>,
+
+    /// Weights of the features searching strategies score positions by.
+    pub weights: Weights,
+
+    /// Time (in milliseconds) to assume the engine waits for a response, if it doesn't
+    /// say.
+    pub default_timeout: u32,
+
+    /// Time (in milliseconds) kept back from the engine's timeout for the response to get
+    /// back to it.
+    pub latency_margin: u32,
+
+    /// How much Monte Carlo tree search favours trying moves it hasn't tried much over
+    /// moves that have done well so far.
+    pub mcts_exploration: f64,
+
+    /// Most iterations Monte Carlo tree search runs per move. It stops short of this if it
+    /// runs out of time.
+    pub mcts_iterations: u32,
+
+    /// How Monte Carlo tree search plays games out.
+    pub mcts_rollout: Rollout,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            hunger_threshold: 40,
+            search_depth: 12,
+            search_opponents: 2,
+            search_table_size: 1 << 16,
+            search_threads: None,
+            weights: Default::default(),
+            default_timeout: DEFAULT_TIMEOUT,
+            latency_margin: 150,
+            mcts_exploration: std::f64::consts::SQRT_2,
+            mcts_iterations: 100_000,
+            mcts_rollout: Rollout::Heuristic,
+        }
+    }
+}
+
+/// Hooks called by the snake endpoint handlers over the course of a game.
+///
+/// Strategies are shared between the server's worker threads, so any per-game state
+/// needs to be kept behind a lock.
+pub trait Strategy: Send + Sync {
+
+    /// Called when a game starts.
+    fn start(&self, _config: &SnakeConfig, _map: &Map) {}
+
+    /// Called every turn, with the time the engine's request came in (which is when its
+    /// timeout starts running). Returns the move to make.
+    fn next_move(&self, config: &SnakeConfig, map: &Map, received: Instant) -> Move;
+
+    /// Called when a game ends.
+    fn end(&self, _config: &SnakeConfig, _map: &Map) {}
+
+}
+
+/// Looks up a strategy by name, set up with the given settings. Returns None if there is no
+/// strategy with that name.
+pub fn from_name(name: &str, settings: &Settings) -> Option<Box<dyn Strategy>> {
+    match name {
+        "forager" => Some(Box::new(Forager::new(settings.hunger_threshold))),
+        "mcts" => Some(Box::new(MonteCarlo::new(settings))),
+        "minimax" => Some(Box::new(Minimax::new(settings))),
+        "tailchaser" => Some(Box::new(TailChaser)),
+        _ => None,
+    }
+}
+
+/// Time a strategy has to think about a move, leaving enough of the engine's timeout for
+/// the response to get back to it.
+fn time_budget(config: &SnakeConfig, settings: &Settings) -> Duration {
+    let timeout = config.game.timeout.unwrap_or(settings.default_timeout);
+    Duration::from_millis(timeout.saturating_sub(settings.latency_margin) as u64)
+}
+
+/// Number of threads a searching strategy picks each move with.
+fn search_threads(settings: &Settings) -> usize {
+    settings.search_threads
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |cores| cores.get()))
+        .max(1)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn keeps_latency_margin_from_timeout() {
+        let settings = Settings {
+            default_timeout: 400,
+            latency_margin: 100,
+            ..Default::default()
+        };
+
+        let mut config = SnakeConfig::default();
+        assert_eq!(time_budget(&config, &settings), Duration::from_millis(300));
+
+        config.game.timeout = Some(500);
+        assert_eq!(time_budget(&config, &settings), Duration::from_millis(400));
+
+        config.game.timeout = Some(50);
+        assert_eq!(time_budget(&config, &settings), Duration::from_millis(0));
+    }
+
+}
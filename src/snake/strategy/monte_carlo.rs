@@ -0,0 +1,78 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::Instant;
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use crate::snake::api::SnakeConfig;
+use crate::snake::map::Map;
+use crate::snake::search::Tree;
+use crate::snake::utils::Move;
+use super::{search_threads, time_budget, Settings, Strategy};
+
+/// Plays lots of quick games out from the current state, and picks the move that works
+/// out best across all of them.
+pub struct MonteCarlo {
+    settings: Settings,
+}
+
+impl MonteCarlo {
+
+    pub fn new(settings: &Settings) -> MonteCarlo {
+        MonteCarlo { settings: settings.clone() }
+    }
+
+}
+
+/// Seed for the games played out on a turn. Going by the game and turn means a game can be
+/// played over again move for move, as long as the search doesn't run out of time.
+fn seed(config: &SnakeConfig) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    config.game.id.hash(&mut hasher);
+    config.turn.hash(&mut hasher);
+    config.you.id.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl Strategy for MonteCarlo {
+
+    fn next_move(&self, config: &SnakeConfig, map: &Map, received: Instant) -> Move {
+        let deadline = received + time_budget(config, &self.settings);
+
+        let mut tree = Tree::new(config, self.settings.mcts_exploration, self.settings.mcts_rollout);
+        tree.run_parallel(
+            self.settings.mcts_iterations,
+            Some(deadline),
+            search_threads(&self.settings),
+            &mut StdRng::seed_from_u64(seed(config)),
+        );
+
+        tree.best_move().unwrap_or_else(|| map.find_spacious_move())
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn seeds_by_game_and_turn() {
+        let mut config = SnakeConfig::default();
+        config.game.id = String::from("game");
+        config.turn = 3;
+
+        assert_eq!(seed(&config), seed(&config.clone()));
+
+        let mut next_turn = config.clone();
+        next_turn.turn += 1;
+        assert_ne!(seed(&config), seed(&next_turn));
+
+        let mut other_game = config.clone();
+        other_game.game.id = String::from("other");
+        assert_ne!(seed(&config), seed(&other_game));
+    }
+
+}
@@ -0,0 +1,104 @@
+use std::time::Instant;
+
+use crate::snake::api::SnakeConfig;
+use crate::snake::map::Map;
+use crate::snake::path::shortest_path_to;
+use crate::snake::rules::Mode;
+use crate::snake::utils::Move;
+use super::Strategy;
+
+/// Follows its own tail around the board, which keeps it alive for as long as it has
+/// health left. In constrictor games, where tails never move, it just goes wherever
+/// there's the most room.
+pub struct TailChaser;
+
+impl Strategy for TailChaser {
+
+    fn next_move(&self, config: &SnakeConfig, map: &Map, _received: Instant) -> Move {
+
+        // Chase your tail!
+        let body = &config.you.body;
+        let head = &body[0];
+        let tail = &body[body.len() - 1];
+
+        if body.len() < 3 {
+            // Special case where head and tail are the same node (should only be
+            // first move). Just try and find a direction with room to move around in.
+            return map.find_spacious_move();
+        }
+
+        if map.mode() == Mode::Constrictor {
+            // Our tail stays put, so following it would only run us into it
+            return map.find_spacious_move();
+        }
+
+        // Try to find your tail
+        match shortest_path_to(map, (head.x, head.y), (tail.x, tail.y)) {
+            Some(path) => {
+                // We've already checked that the head and tail are not the same node,
+                // so we should have more than one node in our path
+                assert!(path[0].next_move.is_some());
+                path[0].next_move.unwrap_or(Move::Left)
+            },
+            None => {
+                // No way to find your tail, so go wherever there's the most room
+                map.find_spacious_move()
+            }
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::snake::api::*;
+
+    #[test]
+    fn follows_tail() {
+        // Board state:
+        //   - - - -
+        //   - H S -
+        //   - T S -
+        //   - - - -
+        //
+        // The tail is right below the head, so we should move down.
+        let body = vec!(
+            Coords { x: 1, y: 1 },
+            Coords { x: 2, y: 1 },
+            Coords { x: 2, y: 2 },
+            Coords { x: 1, y: 2 },
+        );
+
+        let config = SnakeConfig {
+            board: Board {
+                width: 4,
+                height: 4,
+                snakes: vec!(
+                    Snake {
+                        body: body.clone(),
+                        ..Default::default()
+                    }
+                ),
+                ..Default::default()
+            },
+            you: Snake {
+                body,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let map = Map::new(&config);
+        assert_eq!(TailChaser.next_move(&config, &map, Instant::now()), Move::Down);
+
+        // In constrictor games the tail never moves out of the way
+        let mut config = config;
+        config.game.ruleset.name = String::from("constrictor");
+
+        let map = Map::new(&config);
+        assert_ne!(TailChaser.next_move(&config, &map, Instant::now()), Move::Down);
+    }
+
+}
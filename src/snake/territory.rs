@@ -0,0 +1,279 @@
+//
+// Board control. Splits the board up between snakes by which of them can get to each
+// cell first.
+//
+
+use std::collections::HashMap;
+
+use super::api::SnakeConfig;
+use super::map::{BoardSpace, Map};
+use super::utils::TwoDimensionalMap;
+
+/// Which snake gets to a cell first.
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub enum Owner {
+    /// No snake can get to the cell (or it's occupied and never frees up).
+    #[default]
+    Unclaimed,
+    /// Index of the snake on the board that gets to the cell first.
+    Snake(usize),
+    /// Several snakes get to the cell at the same time.
+    Contested,
+}
+
+/// Cells a snake gets to before anyone else.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Claim {
+    pub id: String,
+
+    /// Number of cells in the snake's territory.
+    pub cells: u32,
+
+    /// Number of those cells with food on them.
+    pub food: u32,
+}
+
+pub struct Territory {
+    owners: TwoDimensionalMap<Owner>,
+
+    /// Claims of each snake, in the same order as the snakes on the board.
+    pub claims: Vec<Claim>,
+}
+
+impl Territory {
+
+    /// Splits the board up between snakes, giving each cell to the snake whose head can
+    /// get there in the fewest moves. Cells are only claimed once they're free of snakes,
+    /// the same as for any other search over the map, but a snake that gets next to a cell
+    /// before then can wait around for it.
+    pub fn new(config: &SnakeConfig, map: &Map) -> Territory {
+        let board = &config.board;
+        let mut owners = TwoDimensionalMap::new(board.width as usize, board.height as usize);
+
+        let mut claims: Vec<Claim> = board.snakes.iter()
+            .map(|snake| Claim { id: snake.id.clone(), cells: 0, food: 0 })
+            .collect();
+
+        // Search outwards from every head at once, one move at a time
+        let mut frontier: Vec<((u32, u32), usize)> = board.snakes.iter()
+            .enumerate()
+            .map(|(idx, snake)| ((snake.body[0].x, snake.body[0].y), idx))
+            .collect();
+
+        // Occupied cells next to a snake's territory, and who's waiting on them
+        let mut waiting: HashMap<(u32, u32), Owner> = HashMap::new();
+
+        let mut turns = 0;
+        while !frontier.is_empty() || !waiting.is_empty() {
+            turns += 1;
+
+            let mut reached: HashMap<(u32, u32), Owner> = HashMap::new();
+
+            // Everyone waiting on a cell gets there as soon as it frees up
+            waiting.retain(|coords, owner| {
+                if map.is_passable(*coords, turns) {
+                    reached.insert(*coords, *owner);
+                    false
+                }
+                else {
+                    true
+                }
+            });
+
+            for (coords, idx) in frontier {
+                for next in map.neighbours(coords) {
+                    if owners[(next.0 as usize, next.1 as usize)] != Owner::Unclaimed {
+                        continue;
+                    }
+
+                    let cells = if map.is_passable(next, turns) {
+                        &mut reached
+                    }
+                    else if map.free_at(next.0, next.1) != u32::MAX {
+                        &mut waiting
+                    }
+                    else {
+                        continue;
+                    };
+
+                    let owner = cells.entry(next).or_insert(Owner::Snake(idx));
+                    if *owner != Owner::Snake(idx) {
+                        *owner = Owner::Contested;
+                    }
+                }
+            }
+
+            // Contested cells don't belong to anyone, so nobody's territory grows out
+            // from them
+            frontier = Vec::with_capacity(reached.len());
+            for (coords, owner) in reached {
+                owners[(coords.0 as usize, coords.1 as usize)] = owner;
+
+                if let Owner::Snake(idx) = owner {
+                    claims[idx].cells += 1;
+                    if map.at(coords.0, coords.1) == BoardSpace::FOOD {
+                        claims[idx].food += 1;
+                    }
+
+                    frontier.push((coords, idx));
+                }
+            }
+        }
+
+        Territory { owners, claims }
+    }
+
+    /// Who gets to a cell first.
+    pub fn owner(&self, x: u32, y: u32) -> Owner {
+        self.owners[(x as usize, y as usize)]
+    }
+
+    /// Claim of the snake with the given id, if it's on the board.
+    pub fn claim_of(&self, id: &str) -> Option<&Claim> {
+        self.claims.iter().find(|claim| claim.id == id)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::snake::api::*;
+
+    fn snake(id: &str, body: Vec<Coords>) -> Snake {
+        Snake {
+            id: String::from(id),
+            body,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn splits_board_between_heads() {
+        // Board state:
+        //   A - - - B
+        //   A - - - B
+        //   - - F - F
+        //
+        // The middle column is as far from both heads, so it's contested. The food on
+        // the right is B's.
+        let config = SnakeConfig {
+            board: Board {
+                width: 5,
+                height: 3,
+                food: vec!(Coords { x: 2, y: 2 }, Coords { x: 4, y: 2 }),
+                snakes: vec!(
+                    snake("a", vec!(Coords { x: 0, y: 0 }, Coords { x: 0, y: 1 })),
+                    snake("b", vec!(Coords { x: 4, y: 0 }, Coords { x: 4, y: 1 })),
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let map = Map::new(&config);
+        let territory = Territory::new(&config, &map);
+
+        for y in 0..3 {
+            assert_eq!(territory.owner(2, y), Owner::Contested);
+        }
+        assert_eq!(territory.owner(1, 2), Owner::Snake(0));
+        assert_eq!(territory.owner(3, 2), Owner::Snake(1));
+
+        // Each snake gets the two columns on its side, including the cells its body moves
+        // out of
+        assert_eq!(territory.claim_of("a"), Some(&Claim { id: String::from("a"), cells: 6, food: 0 }));
+        assert_eq!(territory.claim_of("b"), Some(&Claim { id: String::from("b"), cells: 6, food: 1 }));
+    }
+
+    #[test]
+    fn walls_cut_off_territory() {
+        // Board state:
+        //   A - S - B
+        //   - - S - -
+        //   - - S - -
+        //   - - W - -
+        //
+        // The wall (with its head at W) stays put long enough that A and B can't get to
+        // each other's side. Once it moves on, they get to it at the same time.
+        let mut wall = vec!(
+            Coords { x: 2, y: 3 },
+            Coords { x: 2, y: 2 },
+            Coords { x: 2, y: 1 },
+            Coords { x: 2, y: 0 },
+        );
+        wall.resize(12, Coords { x: 2, y: 0 });
+
+        let config = SnakeConfig {
+            board: Board {
+                width: 5,
+                height: 4,
+                snakes: vec!(
+                    snake("a", vec!(Coords { x: 0, y: 0 })),
+                    snake("wall", wall),
+                    snake("b", vec!(Coords { x: 4, y: 0 })),
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let map = Map::new(&config);
+        let territory = Territory::new(&config, &map);
+
+        for y in 0..4 {
+            for x in 0..2 {
+                assert_ne!(territory.owner(x, y), Owner::Snake(2));
+                assert_ne!(territory.owner(4 - x, y), Owner::Snake(0));
+            }
+            assert_ne!(territory.owner(2, y), Owner::Snake(0));
+            assert_ne!(territory.owner(2, y), Owner::Snake(2));
+        }
+
+        assert!(territory.claim_of("a").unwrap().cells > 0);
+        assert_eq!(territory.claim_of("a").unwrap().cells, territory.claim_of("b").unwrap().cells);
+        assert!(territory.claim_of("missing").is_none());
+    }
+
+    #[test]
+    fn waits_for_cells_to_free_up() {
+        // Board state:
+        //   A S -
+        //   - S -
+        //   - S -
+        //   - S -
+        //   - B -
+        //
+        // B's tail is stacked up at the top, and frees up before B can get around to it.
+        // A has room to wait for it, so it gets there first.
+        let mut body = vec!(
+            Coords { x: 1, y: 4 },
+            Coords { x: 1, y: 3 },
+            Coords { x: 1, y: 2 },
+            Coords { x: 1, y: 1 },
+        );
+        body.resize(9, Coords { x: 1, y: 0 });
+
+        let config = SnakeConfig {
+            board: Board {
+                width: 3,
+                height: 5,
+                snakes: vec!(
+                    snake("a", vec!(Coords { x: 0, y: 0 })),
+                    snake("b", body),
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let map = Map::new(&config);
+        let territory = Territory::new(&config, &map);
+
+        assert_eq!(territory.owner(0, 2), Owner::Snake(0));
+        assert_eq!(territory.owner(2, 0), Owner::Snake(1));
+        assert_eq!(territory.owner(1, 0), Owner::Snake(0));
+    }
+
+}
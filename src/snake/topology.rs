@@ -0,0 +1,154 @@
+//
+// Board topologies. Standard boards are bounded by walls, while wrapped boards join each
+// edge to the opposite one, so that moving off one side of the board comes back on the
+// other.
+//
+
+use super::api::SnakeConfig;
+use super::rules::Mode;
+use super::utils::Move;
+
+/// Whether the edges of the board are walls.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum Shape {
+    /// Moving off the board is out of bounds.
+    #[default]
+    Bounded,
+    /// Moving off one edge of the board comes back on at the opposite edge.
+    Torus,
+}
+
+impl Shape {
+
+    /// Shape of the board in a game mode.
+    pub fn of(mode: Mode) -> Shape {
+        match mode {
+            Mode::Wrapped => Shape::Torus,
+            _ => Shape::Bounded,
+        }
+    }
+
+}
+
+/// How the cells of a board join up.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Topology {
+    pub width: u32,
+    pub height: u32,
+    pub shape: Shape,
+}
+
+impl Topology {
+
+    pub fn new(width: u32, height: u32, shape: Shape) -> Topology {
+        Topology { width, height, shape }
+    }
+
+    /// Topology of the board in a game, going by the game's mode.
+    pub fn of(config: &SnakeConfig) -> Topology {
+        let shape = Shape::of(Mode::from_name(&config.game.ruleset.name));
+        Topology::new(config.board.width, config.board.height, shape)
+    }
+
+    /// Cell one step away in the given direction, or None if that's off the board.
+    pub fn step(&self, coords: (u32, u32), move_val: Move) -> Option<(u32, u32)> {
+        let (x, y) = coords;
+        let (width, height) = (self.width, self.height);
+
+        match self.shape {
+            Shape::Bounded => match move_val {
+                Move::Up if y != 0 => Some((x, y - 1)),
+                Move::Down if y + 1 < height => Some((x, y + 1)),
+                Move::Left if x != 0 => Some((x - 1, y)),
+                Move::Right if x + 1 < width => Some((x + 1, y)),
+                _ => None,
+            },
+            Shape::Torus => match move_val {
+                Move::Up => Some((x, (y + height - 1) % height)),
+                Move::Down => Some((x, (y + 1) % height)),
+                Move::Left => Some(((x + width - 1) % width, y)),
+                Move::Right => Some(((x + 1) % width, y)),
+            },
+        }
+    }
+
+    /// Cells next to the given cell, in the order right, left, up, down.
+    pub fn neighbours(&self, coords: (u32, u32)) -> Vec<(u32, u32)> {
+        let mut neighbours = Vec::with_capacity(4);
+
+        for move_val in [Move::Right, Move::Left, Move::Up, Move::Down].iter() {
+            if let Some(next) = self.step(coords, *move_val) {
+                // Going either way around a narrow torus can end up in the same cell
+                if !neighbours.contains(&next) {
+                    neighbours.push(next);
+                }
+            }
+        }
+
+        neighbours
+    }
+
+    /// Fewest moves between two cells, ignoring anything in the way.
+    pub fn distance(&self, a: (u32, u32), b: (u32, u32)) -> u32 {
+        let (dx, dy) = (a.0.abs_diff(b.0), a.1.abs_diff(b.1));
+
+        match self.shape {
+            Shape::Bounded => dx + dy,
+            Shape::Torus => dx.min(self.width - dx) + dy.min(self.height - dy),
+        }
+    }
+
+    /// Fewest moves from a cell to the edge of the board, or None if the board has no
+    /// edges.
+    pub fn edge_distance(&self, coords: (u32, u32)) -> Option<u32> {
+        match self.shape {
+            Shape::Bounded => {
+                let (x, y) = coords;
+                Some(x.min(y).min(self.width - 1 - x).min(self.height - 1 - y))
+            },
+            Shape::Torus => None,
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn bounded_edges_are_walls() {
+        let topology = Topology::new(4, 3, Shape::Bounded);
+
+        assert_eq!(topology.step((0, 0), Move::Left), None);
+        assert_eq!(topology.step((0, 0), Move::Up), None);
+        assert_eq!(topology.step((3, 2), Move::Right), None);
+        assert_eq!(topology.step((3, 2), Move::Down), None);
+        assert_eq!(topology.step((1, 1), Move::Up), Some((1, 0)));
+
+        assert_eq!(topology.neighbours((0, 0)), vec!((1, 0), (0, 1)));
+        assert_eq!(topology.distance((0, 0), (3, 2)), 5);
+        assert_eq!(topology.edge_distance((1, 1)), Some(1));
+    }
+
+    #[test]
+    fn torus_edges_lead_around() {
+        let topology = Topology::new(4, 3, Shape::Torus);
+
+        assert_eq!(topology.step((0, 0), Move::Left), Some((3, 0)));
+        assert_eq!(topology.step((0, 0), Move::Up), Some((0, 2)));
+        assert_eq!(topology.step((3, 2), Move::Right), Some((0, 2)));
+        assert_eq!(topology.step((3, 2), Move::Down), Some((3, 0)));
+
+        assert_eq!(topology.neighbours((0, 0)), vec!((1, 0), (3, 0), (0, 2), (0, 1)));
+        assert_eq!(topology.distance((0, 0), (3, 2)), 2);
+        assert_eq!(topology.distance((0, 0), (2, 1)), 3);
+        assert_eq!(topology.edge_distance((1, 1)), None);
+
+        // On a board two cells wide, left and right lead to the same cell
+        let narrow = Topology::new(2, 3, Shape::Torus);
+        assert_eq!(narrow.neighbours((0, 1)), vec!((1, 1), (0, 0), (0, 2)));
+    }
+
+}
@@ -1,4 +1,5 @@
 use std::ops::{Index, IndexMut};
+use std::str::FromStr;
 
 /// Potential moves a snake can make
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -11,7 +12,7 @@ pub enum Move {
 
 impl Move {
 
-    pub fn to_string(&self) -> &'static str {
+    pub fn to_string(self) -> &'static str {
         match self {
             Move::Up => "up",
             Move::Down => "down",
@@ -22,6 +23,22 @@ impl Move {
 
 }
 
+impl FromStr for Move {
+
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Move, String> {
+        match name {
+            "up" => Ok(Move::Up),
+            "down" => Ok(Move::Down),
+            "left" => Ok(Move::Left),
+            "right" => Ok(Move::Right),
+            _ => Err(format!("Unknown move '{}'", name)),
+        }
+    }
+
+}
+
 /// Simple two-dimensional array implementation
 pub struct TwoDimensionalMap<T: Default + Clone> {
     width: usize,
@@ -39,7 +56,7 @@ impl <T: Default + Clone> Index<(usize, usize)> for TwoDimensionalMap<T> {
 
         assert!(x < self.width);
         assert!(y < self.height);
-        &self.vals[y * self.height + x]
+        &self.vals[y * self.width + x]
     }
 
 }
@@ -52,7 +69,7 @@ impl <T: Default + Clone> IndexMut<(usize, usize)> for TwoDimensionalMap<T> {
 
         assert!(x < self.width);
         assert!(y < self.height);
-        &mut self.vals[y * self.height + x]
+        &mut self.vals[y * self.width + x]
     }
 
 }
@@ -62,8 +79,8 @@ impl <T: Default + Clone> TwoDimensionalMap<T> {
     pub fn new(width: usize, height: usize) -> TwoDimensionalMap<T>
     {
         TwoDimensionalMap {
-            width: width,
-            height: height,
+            width,
+            height,
             vals: vec![Default::default(); width * height]
         }
     }
@@ -75,6 +92,15 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn moves_round_trip_through_strings() {
+        for move_val in [Move::Up, Move::Down, Move::Left, Move::Right].iter() {
+            assert_eq!(move_val.to_string().parse::<Move>(), Ok(*move_val));
+        }
+
+        assert!("sideways".parse::<Move>().is_err());
+    }
+
     #[test]
     fn should_be_able_to_add_values() {
         let mut arr = TwoDimensionalMap::<i32>::new(30, 30);
@@ -86,12 +112,23 @@ mod tests {
         assert_eq!(arr[(13, 9)], -12);
     }
 
+    #[test]
+    fn should_keep_values_apart_on_non_square_maps() {
+        let mut arr = TwoDimensionalMap::<i32>::new(4, 3);
+
+        arr[(3, 1)] = 1;
+        arr[(0, 2)] = 2;
+
+        assert_eq!(arr[(3, 1)], 1);
+        assert_eq!(arr[(0, 2)], 2);
+    }
+
     #[test]
     #[should_panic]
     fn should_panic_when_out_of_bounds_requested_x() {
         let arr = TwoDimensionalMap::<i32>::new(30, 30);
         
-        arr[(31, 15)];
+        let _ = arr[(31, 15)];
     }
 
     #[test]
@@ -99,7 +136,7 @@ mod tests {
     fn should_panic_when_out_of_bounds_requested_y() {
         let arr = TwoDimensionalMap::<i32>::new(30, 30);
 
-        arr[(15, 31)];
+        let _ = arr[(15, 31)];
     }
 
 }